@@ -1,37 +1,133 @@
 use crate::models::{Account, AccountError};
 use std::fs::File;
-use std::io::{BufRead, BufReader};
-use std::path::Path;
+use std::io::{self, BufRead, BufReader};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
+use thiserror::Error;
 
-pub fn parse_data_file(path: String) -> Result<(String, Vec<Account>), AccountError> {
-    let lines = lines_from_file(path);
-    let iv = lines.get(1).expect("IV missing from the file");
-    let mut account_vec: Vec<Account> = Vec::new();
-    for line in lines.iter().skip(2) {
-        let account = Account::from_str(line)?;
-        account_vec.push(account);
-    }
-    Ok((iv.clone(), account_vec))
+#[derive(Error, Debug)]
+pub enum FsioError {
+    #[error("{}: {source}", path.display())]
+    Io { path: PathBuf, source: io::Error },
+    #[error("{}: IV missing from the file", path.display())]
+    MissingIv { path: PathBuf },
+    /// `line` is 1-based, as shown by editors.
+    #[error("{}:{line}: {source}", path.display())]
+    Account {
+        path: PathBuf,
+        line: usize,
+        source: AccountError,
+    },
+}
+
+pub fn parse_data_file(path: impl AsRef<Path>) -> Result<(String, Vec<Account>), FsioError> {
+    let path = path.as_ref();
+    let lines = lines_from_file(path)?;
+    let iv = lines.get(1).ok_or_else(|| FsioError::MissingIv {
+        path: path.to_path_buf(),
+    })?;
+    let accounts = parse_accounts(path, &lines, 2)?;
+    Ok((iv.clone(), accounts))
 }
 
-pub fn parse_plain_file(path: String) -> Result<Vec<Account>, AccountError> {
-    let lines = lines_from_file(path);
+pub fn parse_plain_file(path: impl AsRef<Path>) -> Result<Vec<Account>, FsioError> {
+    let path = path.as_ref();
+    let lines = lines_from_file(path)?;
+    parse_accounts(path, &lines, 0)
+}
+
+fn parse_accounts(path: &Path, lines: &[String], skip: usize) -> Result<Vec<Account>, FsioError> {
     let mut account_vec: Vec<Account> = Vec::new();
-    for line in &lines {
-        let account = Account::from_str(line)?;
+    for (index, line) in lines.iter().enumerate().skip(skip) {
+        let account = Account::from_str(line).map_err(|source| FsioError::Account {
+            path: path.to_path_buf(),
+            line: index + 1,
+            source,
+        })?;
         account_vec.push(account);
     }
     Ok(account_vec)
 }
 
-fn lines_from_file<P>(filename: P) -> Vec<String>
-where
-    P: AsRef<Path>,
-{
-    let file = File::open(filename).expect("invalid file");
-    let buf = BufReader::new(file);
-    buf.lines()
-        .map(|l| l.expect("Couldn't parse line"))
-        .collect()
+fn lines_from_file(path: &Path) -> Result<Vec<String>, FsioError> {
+    let io_error = |source| FsioError::Io {
+        path: path.to_path_buf(),
+        source,
+    };
+    let file = File::open(path).map_err(io_error)?;
+    BufReader::new(file)
+        .lines()
+        .collect::<Result<_, _>>()
+        .map_err(io_error)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn temp_file(name: &str, contents: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!("twofa-rs-{}-{name}", std::process::id()));
+        fs::write(&path, contents).unwrap();
+        path
+    }
+
+    #[test]
+    fn broken_line_is_reported() {
+        let path = temp_file(
+            "plain.txt",
+            "otpauth://totp/ACME:john?secret=GEZDGNBVGY3TQOJQ&issuer=ACME\n\
+             otpauth://totp/ACME:jane?issuer=ACME\n",
+        );
+        let err = parse_plain_file(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        match &err {
+            FsioError::Account { line, source, .. } => {
+                assert_eq!(*line, 2);
+                assert!(matches!(source, AccountError::MissingSecret { .. }));
+            }
+            _ => panic!("unexpected error {err}"),
+        }
+        assert!(err.to_string().contains("plain.txt:2: "));
+    }
+
+    #[test]
+    fn vendor_parameters_are_kept() {
+        let path = temp_file(
+            "vendor.txt",
+            "otpauth://totp/ACME:john?secret=GEZDGNBVGY3TQOJQ&issuer=ACME\
+             &image=https%3A%2F%2Facme.co%2Flogo.png\n",
+        );
+        let accounts = parse_plain_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(
+            accounts[0].parameters().extra_parameters(),
+            [("image".to_string(), "https://acme.co/logo.png".to_string())]
+        );
+        assert!(accounts[0]
+            .to_string()
+            .ends_with("&image=https%3A%2F%2Facme.co%2Flogo.png"));
+    }
+
+    #[test]
+    fn data_file() {
+        let path = temp_file(
+            "data.txt",
+            "header\n0011223344\notpauth://totp/ACME:john?secret=GEZDGNBVGY3TQOJQ&issuer=ACME\n",
+        );
+        let (iv, accounts) = parse_data_file(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(iv, "0011223344");
+        assert_eq!(accounts.len(), 1);
+
+        let path = temp_file("no-iv.txt", "header\n");
+        let err = parse_data_file(&path).err().unwrap();
+        fs::remove_file(&path).unwrap();
+        assert!(matches!(err, FsioError::MissingIv { .. }));
+
+        assert!(matches!(
+            parse_plain_file(std::env::temp_dir().join("twofa-rs-missing")),
+            Err(FsioError::Io { .. })
+        ));
+    }
 }
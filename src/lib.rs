@@ -0,0 +1,3 @@
+pub mod fsio;
+pub mod models;
+pub mod token;
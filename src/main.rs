@@ -1,8 +1,6 @@
-mod fsio;
-mod models;
-mod token;
-use models::{Account, HmacHash};
 use std::str::FromStr;
+use twofa_rs::models::{Account, HmacHash};
+use twofa_rs::token;
 
 fn main() {
     let hmac_token = token::hmac::create_token();
@@ -15,19 +13,23 @@ fn main() {
     let hex_encoded_message = "7768617420646f2079612077616e7420\
                                666f72206e6f7468696e673f"; /*what do ya want for nothing?*/
 
-    let sha1 = token::hmac::compute_hmac(hmac_secret_key, hex_encoded_message, HmacHash::SHA1);
+    let sha1 =
+        token::hmac::compute_hmac(hmac_secret_key, hex_encoded_message, HmacHash::SHA1).unwrap();
     println!("{}", sha1);
 
-    let sha256 = token::hmac::compute_hmac(hmac_secret_key, hex_encoded_message, HmacHash::SHA256);
+    let sha256 =
+        token::hmac::compute_hmac(hmac_secret_key, hex_encoded_message, HmacHash::SHA256).unwrap();
     println!("{}", sha256);
 
-    let sha512 = token::hmac::compute_hmac(hmac_secret_key, hex_encoded_message, HmacHash::SHA512);
+    let sha512 =
+        token::hmac::compute_hmac(hmac_secret_key, hex_encoded_message, HmacHash::SHA512).unwrap();
     println!("{}", sha512);
 
     let hotp_secret_key = "12345678901234567890";
     let code_digits = 6;
     for counter in 0..10i64 {
-        let sha1 = token::otp::compute_hotp(hotp_secret_key, counter, code_digits, HmacHash::SHA1);
+        let sha1 = token::otp::compute_hotp(hotp_secret_key, counter, code_digits, HmacHash::SHA1)
+            .unwrap();
         println!("{}", sha1);
     }
 
@@ -48,6 +50,7 @@ fn main() {
         assert_eq!(
             item.1,
             token::otp::compute_totp(&hex_key, item.0, code_digits, hash_algorithm, period)
+                .unwrap()
         );
     }
     println!("Matched all totp cases");
@@ -59,6 +62,16 @@ fn main() {
     let mut acc1 = Account::from_str(uri).unwrap();
     let acc2 = Account::from_str(hotp_uri).unwrap();
     println!("{acc1}\n{acc2}");
-    acc1.update_secret_key("AAGAYEMERIMAUTKATAMASHADEKHNE".to_string());
+    println!(
+        "{} ({}s left)",
+        acc1.current_code().unwrap(),
+        acc1.remaining_seconds().unwrap().unwrap_or(0)
+    );
+    if let Some(window) = acc1.code_window().unwrap() {
+        println!("next: {}", window.next.code);
+    }
+    println!("{}", acc2.current_code().unwrap());
+    acc1.set_secret_key("AAGAYEMERIMAUTKATAMASHADEKHNE")
+        .unwrap();
     println!("{acc1}");
 }
@@ -0,0 +1,221 @@
+use crate::models::{
+    check_code_digits, check_counter, check_hash_algorithm, check_issuer, check_secret_key,
+    check_step_period, Account, AccountError, HmacHash, OtpType, Parameters, DEFAULT_STEP_PERIOD,
+};
+use crate::token::otp::{self, OtpEncoding};
+
+/// Builds an account from its parts, with the checks a strict parse of
+/// its URI would make. The label issuer is the issuer.
+pub struct AccountBuilder {
+    otp_type: OtpType,
+    issuer: String,
+    account_name: String,
+    secret_key: String,
+    hash_algorithm: HmacHash,
+    code_digits: u8,
+    counter: Option<i64>,
+    step_period: u32,
+    start_time: i64,
+    encoding: OtpEncoding,
+}
+
+impl AccountBuilder {
+    /// Steam accounts start with the Steam Guard digits and encoding.
+    /// The issuer and account name are trimmed, as they are when parsing.
+    pub fn new(otp_type: OtpType, issuer: &str, account_name: &str) -> Self {
+        let (code_digits, encoding) = match otp_type {
+            OtpType::STEAM => (otp::STEAM_DIGITS, OtpEncoding::steam()),
+            OtpType::HOTP | OtpType::TOTP => (6, OtpEncoding::Decimal),
+        };
+        AccountBuilder {
+            otp_type,
+            issuer: issuer.trim().to_string(),
+            account_name: account_name.trim().to_string(),
+            secret_key: String::new(),
+            hash_algorithm: HmacHash::SHA1,
+            code_digits,
+            counter: None,
+            step_period: DEFAULT_STEP_PERIOD,
+            start_time: 0,
+            encoding,
+        }
+    }
+
+    /// The base32 encoded secret, without padding or separators.
+    pub fn with_secret_key(mut self, secret_key: &str) -> Self {
+        self.secret_key = secret_key.to_string();
+        self
+    }
+
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HmacHash) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    pub fn with_digits(mut self, code_digits: u8) -> Self {
+        self.code_digits = code_digits;
+        self
+    }
+
+    /// Required for HOTP accounts.
+    pub fn with_counter(mut self, counter: i64) -> Self {
+        self.counter = Some(counter);
+        self
+    }
+
+    pub fn with_step_period(mut self, step_period: u32) -> Self {
+        self.step_period = step_period;
+        self
+    }
+
+    /// T0, the unix time TOTP timesteps are counted from.
+    pub fn with_start_time(mut self, start_time: i64) -> Self {
+        self.start_time = start_time;
+        self
+    }
+
+    pub fn with_encoding(mut self, encoding: OtpEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn build(self) -> Result<Account, AccountError> {
+        check_issuer(&self.issuer)?;
+        check_secret_key(&self.secret_key)?;
+        check_hash_algorithm(self.otp_type, self.hash_algorithm)?;
+        check_code_digits(self.otp_type, self.code_digits)?;
+        check_step_period(self.step_period)?;
+        match (self.otp_type, self.counter) {
+            (_, Some(counter)) => check_counter(counter)?,
+            (OtpType::HOTP, None) => {
+                return Err(AccountError::InvalidParameter {
+                    name: "counter",
+                    reason: "is required for hotp accounts".to_string(),
+                    span: None,
+                })
+            }
+            (OtpType::TOTP | OtpType::STEAM, None) => (),
+        }
+        if self.otp_type == OtpType::STEAM && self.encoding != OtpEncoding::steam() {
+            return Err(AccountError::InvalidParameter {
+                name: "encoding",
+                reason: "steam accounts always use the Steam Guard alphabet".to_string(),
+                span: None,
+            });
+        }
+
+        Ok(Account {
+            protocol: "otpauth".to_string(),
+            otp_type: self.otp_type,
+            label_issuer: self.issuer.clone(),
+            label_account_name: self.account_name,
+            parameters: Parameters {
+                secret_key: self.secret_key,
+                issuer: self.issuer,
+                hash_algorithm: self.hash_algorithm,
+                code_digits: self.code_digits,
+                counter: self.counter,
+                step_period: self.step_period,
+                start_time: self.start_time,
+                encoding: self.encoding,
+                extra: Vec::new(),
+            },
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    const SECRET: &str = "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ";
+
+    #[test]
+    fn build_accounts() {
+        let account = AccountBuilder::new(OtpType::HOTP, "ACME Co", "john.doe@email.com")
+            .with_secret_key(SECRET)
+            .with_hash_algorithm(HmacHash::SHA256)
+            .with_digits(8)
+            .with_counter(1)
+            .build()
+            .unwrap();
+        assert_eq!(account.otp_type(), OtpType::HOTP);
+        assert_eq!(account.label_issuer(), "ACME Co");
+        assert_eq!(account.account_name(), "john.doe@email.com");
+        assert_eq!(account.parameters().issuer(), "ACME Co");
+        assert_eq!(account.parameters().counter(), Some(1));
+        assert_eq!(
+            account.to_string(),
+            "otpauth://hotp/ACME%20Co:john.doe@email.com?\
+             secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME%20Co&algorithm=SHA256&digits=8&counter=1"
+        );
+        assert_eq!(Account::from_str(&account.to_string()).unwrap(), account);
+
+        let account = AccountBuilder::new(OtpType::STEAM, "Steam", "john.doe")
+            .with_secret_key(SECRET)
+            .build()
+            .unwrap();
+        assert_eq!(account.code_at(59).unwrap(), "PV9M4");
+
+        let account = AccountBuilder::new(OtpType::TOTP, "ACME", "john")
+            .with_secret_key(SECRET)
+            .with_digits(8)
+            .with_start_time(-30)
+            .build()
+            .unwrap();
+        assert_eq!(account.parameters().start_time(), -30);
+        assert_eq!(account.code_at(0).unwrap(), "94287082");
+        assert_eq!(Account::from_str(&account.to_string()).unwrap(), account);
+    }
+
+    #[test]
+    fn build_validation() {
+        let totp = || AccountBuilder::new(OtpType::TOTP, "ACME", "john").with_secret_key(SECRET);
+        assert!(totp().build().is_ok());
+        let account = AccountBuilder::new(OtpType::TOTP, " ACME ", " john")
+            .with_secret_key(SECRET)
+            .build()
+            .unwrap();
+        assert_eq!(account.label_issuer(), "ACME");
+        assert_eq!(account.account_name(), "john");
+        assert_eq!(Account::from_str(&account.to_string()).unwrap(), account);
+        assert!(totp().with_secret_key("").build().is_err());
+        assert!(totp().with_secret_key("GEZDGNBV====").build().is_err());
+        assert!(totp().with_secret_key("GEZD GNBV").build().is_err());
+        assert!(totp().with_secret_key("GEZDGNB1").build().is_err());
+        assert!(totp().with_digits(0).build().is_err());
+        assert!(totp().with_digits(11).build().is_err());
+        assert!(totp().with_step_period(0).build().is_err());
+        assert!(totp().with_counter(-1).build().is_err());
+        // Steam's alphabet alone doesn't make a steam account
+        let account = totp().with_encoding(OtpEncoding::steam()).build().unwrap();
+        assert_eq!(account.otp_type(), OtpType::TOTP);
+        assert_eq!(Account::from_str(&account.to_string()).unwrap(), account);
+        assert!(AccountBuilder::new(OtpType::TOTP, "", "john")
+            .with_secret_key(SECRET)
+            .build()
+            .is_err());
+        assert!(AccountBuilder::new(OtpType::TOTP, "AC:ME", "john")
+            .with_secret_key(SECRET)
+            .build()
+            .is_err());
+
+        let hotp = AccountBuilder::new(OtpType::HOTP, "ACME", "john").with_secret_key(SECRET);
+        assert!(matches!(
+            hotp.build(),
+            Err(AccountError::InvalidParameter {
+                name: "counter",
+                ..
+            })
+        ));
+
+        let steam = || AccountBuilder::new(OtpType::STEAM, "Steam", "john").with_secret_key(SECRET);
+        assert!(steam().with_digits(6).build().is_err());
+        assert!(steam()
+            .with_hash_algorithm(HmacHash::SHA256)
+            .build()
+            .is_err());
+        assert!(steam().with_encoding(OtpEncoding::Hex).build().is_err());
+    }
+}
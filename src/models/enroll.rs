@@ -0,0 +1,158 @@
+use crate::models::{Account, AccountError, HmacHash, ParseOptions};
+use crate::token::base32;
+use percent_encoding::{utf8_percent_encode, NON_ALPHANUMERIC};
+use ring::rand::{SecureRandom, SystemRandom};
+
+/// 160 bits, the secret length recommended by RFC 4226 section 4.
+pub const DEFAULT_SECRET_LENGTH: usize = 20;
+// RFC 4226 section 4, "The length of the shared secret MUST be at least 128 bits"
+const MIN_SECRET_LENGTH: usize = 16;
+
+/// Creates new TOTP accounts with a random secret.
+///
+/// The account is built by parsing the URI it will be shared as,
+/// so anything handed out can be read back by [`Account::from_str`].
+///
+/// [`Account::from_str`]: std::str::FromStr::from_str
+pub struct Enrollment {
+    issuer: String,
+    account_name: String,
+    hash_algorithm: HmacHash,
+    code_digits: u8,
+    step_period: u32,
+    secret_length: usize,
+}
+
+impl Enrollment {
+    pub fn new(issuer: &str, account_name: &str) -> Self {
+        Enrollment {
+            issuer: issuer.to_string(),
+            account_name: account_name.to_string(),
+            hash_algorithm: HmacHash::SHA1,
+            code_digits: 6,
+            step_period: 30,
+            secret_length: DEFAULT_SECRET_LENGTH,
+        }
+    }
+
+    pub fn with_hash_algorithm(mut self, hash_algorithm: HmacHash) -> Self {
+        self.hash_algorithm = hash_algorithm;
+        self
+    }
+
+    pub fn with_digits(mut self, code_digits: u8) -> Self {
+        self.code_digits = code_digits;
+        self
+    }
+
+    pub fn with_step_period(mut self, step_period: u32) -> Self {
+        self.step_period = step_period;
+        self
+    }
+
+    /// Length of the generated secret in bytes, at least 16.
+    pub fn with_secret_length(mut self, secret_length: usize) -> Self {
+        self.secret_length = secret_length;
+        self
+    }
+
+    /// Builds an account around a secret from the system's secure random source.
+    pub fn generate(&self) -> Result<Account, AccountError> {
+        self.generate_with(&SystemRandom::new())
+    }
+
+    pub fn generate_with(&self, rng: &dyn SecureRandom) -> Result<Account, AccountError> {
+        if self.secret_length < MIN_SECRET_LENGTH {
+            return Err(AccountError::InvalidParameter {
+                name: "secret length",
+                reason: format!("must be at least {MIN_SECRET_LENGTH} bytes"),
+                span: None,
+            });
+        }
+        let mut secret = vec![0u8; self.secret_length];
+        rng.fill(&mut secret)
+            .map_err(|_| AccountError::RandomSecret)?;
+        self.build(&secret)
+    }
+
+    /// Builds an account around an existing secret, e.g. one from another system.
+    pub fn build(&self, secret: &[u8]) -> Result<Account, AccountError> {
+        let issuer = utf8_percent_encode(&self.issuer, NON_ALPHANUMERIC);
+        let uri = format!(
+            "otpauth://totp/{issuer}:{}?secret={}&issuer={issuer}&algorithm={}&digits={}&period={}",
+            utf8_percent_encode(&self.account_name, NON_ALPHANUMERIC),
+            base32::encode(secret),
+            self.hash_algorithm,
+            self.code_digits,
+            self.step_period,
+        );
+        Account::parse_with(&uri, ParseOptions::strict()).map(|(account, _)| account)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::str::FromStr;
+
+    #[test]
+    fn enrollment_uri() {
+        let account = Enrollment::new("ACME Co", "john.doe@email.com")
+            .with_hash_algorithm(HmacHash::SHA256)
+            .with_digits(8)
+            .with_step_period(60)
+            .build(b"12345678901234567890")
+            .unwrap();
+        assert_eq!(
+            account.to_string(),
+            "otpauth://totp/ACME%20Co:john.doe@email.com?\
+             secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME%20Co&algorithm=SHA256&digits=8&period=60"
+        );
+        assert_eq!(
+            account.decoded_secret_key().unwrap(),
+            b"12345678901234567890"
+        );
+    }
+
+    #[test]
+    fn generated_secrets() {
+        let enrollment = Enrollment::new("ACME Co", "john doe");
+        let first = enrollment.generate().unwrap();
+        let second = enrollment.generate().unwrap();
+        assert_eq!(
+            first.decoded_secret_key().unwrap().len(),
+            DEFAULT_SECRET_LENGTH
+        );
+        assert_ne!(
+            first.decoded_secret_key().unwrap(),
+            second.decoded_secret_key().unwrap()
+        );
+
+        let account = enrollment.with_secret_length(32).generate().unwrap();
+        assert_eq!(account.decoded_secret_key().unwrap().len(), 32);
+        // The URI parses back to the same account
+        let parsed = Account::from_str(&account.to_string()).unwrap();
+        assert_eq!(parsed.to_string(), account.to_string());
+        assert_eq!(parsed.label_account_name, "john doe");
+        assert!(account
+            .to_string()
+            .starts_with("otpauth://totp/ACME%20Co:john%20doe?"));
+    }
+
+    #[test]
+    fn invalid_enrollments() {
+        assert!(Enrollment::new("ACME Co", "john.doe")
+            .with_secret_length(10)
+            .generate()
+            .is_err());
+        assert!(Enrollment::new("ACME Co", "john.doe")
+            .with_digits(11)
+            .generate()
+            .is_err());
+        assert!(Enrollment::new("ACME Co", "john.doe")
+            .with_step_period(0)
+            .generate()
+            .is_err());
+        assert!(Enrollment::new("", "john.doe").generate().is_err());
+    }
+}
@@ -1,28 +1,408 @@
-use percent_encoding::{percent_decode_str, utf8_percent_encode, NON_ALPHANUMERIC};
-use regex::Regex;
+pub mod builder;
+pub mod enroll;
+pub mod motp;
+pub mod skey;
+
+use crate::token::base32;
+use crate::token::clock::{Clock, SystemClock};
+use crate::token::otp::{self, CodeAlphabet, CodeWindow, OtpEncoding};
+use crate::token::verify::{Verifier, Window};
+use crate::token::TokenError;
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
 use std::fmt;
+use std::ops::Range;
 use std::str::FromStr;
 use thiserror::Error;
 
+// Account names are usually email addresses, keep them readable
+const ACCOUNT_NAME_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'.')
+    .remove(b'@')
+    .remove(b'-')
+    .remove(b'_')
+    .remove(b'+');
+
+// RFC 3986 section 2.3, "Unreserved Characters" are left as is in query values
+const QUERY_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+const DEFAULT_STEP_PERIOD: u32 = 30;
+
+/// Byte range of the offending part of a parsed URI.
+pub type Span = Range<usize>;
+
 #[derive(Error, Debug)]
 pub enum AccountError {
-    #[error("malformed input: {0}")]
-    Parsing(String),
+    #[error("missing query parameters")]
+    MissingQuery { span: Span },
+    #[error("malformed query parameter {found:?}")]
+    MalformedQuery { found: String, span: Span },
+    #[error("missing scheme, expected otpauth://")]
+    MissingScheme { span: Span },
+    #[error("wrong scheme {found:?}, expected otpauth")]
+    WrongScheme { found: String, span: Span },
+    #[error("missing otp type or label")]
+    MissingLabel { span: Span },
+    #[error("unknown otp type {found:?}")]
+    UnknownOtpType { found: String, span: Span },
+    #[error("label issuer {label_issuer:?} does not match issuer parameter {issuer:?}")]
+    IssuerMismatch {
+        label_issuer: String,
+        issuer: String,
+        span: Span,
+    },
+    /// Parsing gives the span of the value, setters and builders have none.
+    #[error("invalid {name}: {reason}")]
+    InvalidParameter {
+        name: &'static str,
+        reason: String,
+        span: Option<Span>,
+    },
+    #[error("unknown parameter {name:?}")]
+    UnknownParameter { name: String, span: Span },
+    #[error("duplicate parameter {name:?}")]
+    DuplicateParameter { name: String, span: Span },
+    #[error("missing secret parameter")]
+    MissingSecret { span: Span },
+    #[error("missing issuer parameter")]
+    MissingIssuer { span: Span },
+    #[error("hotp account is missing its counter")]
+    MissingCounter { span: Span },
+    #[error("s/key sequence is used up, reinitialise the account")]
+    SequenceExhausted,
+    #[error("code generation failed: {0}")]
+    Token(#[from] TokenError),
+    #[error("could not generate a random secret")]
+    RandomSecret,
+}
+
+impl AccountError {
+    /// Where in the parsed URI the error is, for errors raised while parsing.
+    pub fn span(&self) -> Option<Span> {
+        match self {
+            Self::MissingQuery { span }
+            | Self::MalformedQuery { span, .. }
+            | Self::MissingScheme { span }
+            | Self::WrongScheme { span, .. }
+            | Self::MissingLabel { span }
+            | Self::UnknownOtpType { span, .. }
+            | Self::IssuerMismatch { span, .. }
+            | Self::UnknownParameter { span, .. }
+            | Self::DuplicateParameter { span, .. }
+            | Self::MissingSecret { span }
+            | Self::MissingIssuer { span }
+            | Self::MissingCounter { span } => Some(span.clone()),
+            Self::InvalidParameter { span, .. } => span.clone(),
+            Self::SequenceExhausted | Self::Token(_) | Self::RandomSecret => None,
+        }
+    }
+}
+
+/// How closely a URI has to follow the Key Uri Format.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct ParseOptions {
+    lenient: bool,
+}
+
+impl ParseOptions {
+    /// Every deviation from the format is an error.
+    pub fn strict() -> Self {
+        ParseOptions { lenient: false }
+    }
+
+    /// Accepts the deviations listed in [`ParseWarning`] as found in
+    /// vendor QR codes, reporting each of them instead of failing.
+    /// Used by `from_str`, which drops the warnings.
+    pub fn lenient() -> Self {
+        ParseOptions { lenient: true }
+    }
+
+    pub fn is_lenient(&self) -> bool {
+        self.lenient
+    }
+}
+
+/// A deviation from the Key Uri Format accepted by a lenient parse.
+/// Strict parsing fails with the matching [`AccountError`] instead.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum ParseWarning {
+    /// No `issuer=` parameter, the label issuer is used if there is one.
+    MissingIssuer {
+        span: Span,
+    },
+    LowercaseAlgorithm {
+        value: String,
+        span: Span,
+    },
+    /// Spaces, hyphens or padding in the secret, they are removed.
+    UnnormalizedSecret {
+        value: String,
+        span: Span,
+    },
+    /// The parameter is kept as is but otherwise ignored.
+    UnknownParameter {
+        name: String,
+        span: Span,
+    },
+    /// The last value is used.
+    DuplicateParameter {
+        name: String,
+        span: Span,
+    },
+    /// The `issuer` parameter is used as the account's issuer.
+    IssuerMismatch {
+        label_issuer: String,
+        issuer: String,
+        span: Span,
+    },
+    /// Digits, algorithm, period or encoder of a Steam account that
+    /// Steam Guard doesn't use, Steam's own are used instead.
+    SteamParameter {
+        name: &'static str,
+        value: String,
+        span: Span,
+    },
+}
+
+impl ParseWarning {
+    pub fn span(&self) -> Span {
+        match self {
+            Self::MissingIssuer { span }
+            | Self::LowercaseAlgorithm { span, .. }
+            | Self::UnnormalizedSecret { span, .. }
+            | Self::UnknownParameter { span, .. }
+            | Self::DuplicateParameter { span, .. }
+            | Self::IssuerMismatch { span, .. }
+            | Self::SteamParameter { span, .. } => span.clone(),
+        }
+    }
+}
+
+impl fmt::Display for ParseWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::MissingIssuer { .. } => write!(f, "missing issuer parameter"),
+            Self::LowercaseAlgorithm { value, .. } => {
+                write!(f, "algorithm {value:?} should be uppercase")
+            }
+            Self::UnnormalizedSecret { value, .. } => {
+                write!(f, "secret {value:?} contains separators or padding")
+            }
+            Self::UnknownParameter { name, .. } => write!(f, "unknown parameter {name:?}"),
+            Self::DuplicateParameter { name, .. } => write!(f, "duplicate parameter {name:?}"),
+            Self::IssuerMismatch {
+                label_issuer,
+                issuer,
+                ..
+            } => write!(
+                f,
+                "label issuer {label_issuer:?} does not match issuer parameter {issuer:?}"
+            ),
+            Self::SteamParameter { name, value, .. } => {
+                write!(f, "steam accounts can't use {name} {value:?}")
+            }
+        }
+    }
+}
+
+impl From<ParseWarning> for AccountError {
+    fn from(warning: ParseWarning) -> Self {
+        match warning {
+            ParseWarning::MissingIssuer { span } => Self::MissingIssuer { span },
+            ParseWarning::LowercaseAlgorithm { value, span } => Self::InvalidParameter {
+                name: "algorithm",
+                reason: format!("{value:?} should be uppercase"),
+                span: Some(span),
+            },
+            ParseWarning::UnnormalizedSecret { span, .. } => Self::InvalidParameter {
+                name: "secret",
+                reason: "contains separators or padding".to_string(),
+                span: Some(span),
+            },
+            ParseWarning::UnknownParameter { name, span } => Self::UnknownParameter { name, span },
+            ParseWarning::DuplicateParameter { name, span } => {
+                Self::DuplicateParameter { name, span }
+            }
+            ParseWarning::IssuerMismatch {
+                label_issuer,
+                issuer,
+                span,
+            } => Self::IssuerMismatch {
+                label_issuer,
+                issuer,
+                span,
+            },
+            ParseWarning::SteamParameter { name, value, span } => Self::InvalidParameter {
+                name,
+                reason: format!("steam accounts can't use {value:?}"),
+                span: Some(span),
+            },
+        }
+    }
+}
+
+/// Collects warnings, or turns them into errors when parsing strictly.
+struct Diagnostics {
+    options: ParseOptions,
+    warnings: Vec<ParseWarning>,
+}
+
+impl Diagnostics {
+    fn new(options: ParseOptions) -> Self {
+        Diagnostics {
+            options,
+            warnings: Vec::new(),
+        }
+    }
+
+    fn report(&mut self, warning: ParseWarning) -> Result<(), AccountError> {
+        if !self.options.is_lenient() {
+            return Err(warning.into());
+        }
+        self.warnings.push(warning);
+        Ok(())
+    }
+}
+
+// Checks shared by the setters and `AccountBuilder`, parsing reports
+// the same problems with a span.
+
+fn check_issuer(issuer: &str) -> Result<(), AccountError> {
+    if issuer.is_empty() {
+        return Err(AccountError::InvalidParameter {
+            name: "issuer",
+            reason: "must not be empty".to_string(),
+            span: None,
+        });
+    }
+    if issuer.contains(':') {
+        return Err(AccountError::InvalidParameter {
+            name: "issuer",
+            reason: "must not contain a colon".to_string(),
+            span: None,
+        });
+    }
+    Ok(())
+}
+
+fn check_secret_key(secret_key: &str) -> Result<(), AccountError> {
+    if secret_key.is_empty() {
+        return Err(AccountError::InvalidParameter {
+            name: "secret",
+            reason: "must not be empty".to_string(),
+            span: None,
+        });
+    }
+    // The decoder skips separators and padding, a URI secret has none
+    if !secret_key.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(AccountError::InvalidParameter {
+            name: "secret",
+            reason: "must only contain base32 characters".to_string(),
+            span: None,
+        });
+    }
+    base32::decode(secret_key).map_err(|err| AccountError::InvalidParameter {
+        name: "secret",
+        reason: err.to_string(),
+        span: None,
+    })?;
+    Ok(())
+}
+
+fn check_hash_algorithm(otp_type: OtpType, hash_algorithm: HmacHash) -> Result<(), AccountError> {
+    if otp_type == OtpType::STEAM && hash_algorithm != HmacHash::SHA1 {
+        return Err(AccountError::InvalidParameter {
+            name: "algorithm",
+            reason: "steam accounts always use SHA1".to_string(),
+            span: None,
+        });
+    }
+    Ok(())
+}
+
+fn check_code_digits(otp_type: OtpType, code_digits: u8) -> Result<(), AccountError> {
+    if otp_type == OtpType::STEAM && code_digits != otp::STEAM_DIGITS {
+        return Err(AccountError::InvalidParameter {
+            name: "digits",
+            reason: format!("steam accounts always use {} digits", otp::STEAM_DIGITS),
+            span: None,
+        });
+    }
+    if !(1..=otp::MAX_DIGITS).contains(&code_digits) {
+        return Err(AccountError::InvalidParameter {
+            name: "digits",
+            reason: format!("must be between 1 and {}", otp::MAX_DIGITS),
+            span: None,
+        });
+    }
+    Ok(())
+}
+
+fn check_step_period(step_period: u32) -> Result<(), AccountError> {
+    if step_period == 0 {
+        return Err(AccountError::InvalidParameter {
+            name: "period",
+            reason: "must be greater than 0".to_string(),
+            span: None,
+        });
+    }
+    Ok(())
+}
+
+fn check_counter(counter: i64) -> Result<(), AccountError> {
+    if counter < 0 {
+        return Err(AccountError::InvalidParameter {
+            name: "counter",
+            reason: "must not be negative".to_string(),
+            span: None,
+        });
+    }
+    Ok(())
+}
+
+/// Parses a number made of ASCII digits and, for signed types, a leading `-`.
+/// `from_str` also takes a `+` sign.
+fn parse_decimal<T: FromStr>(value: &str) -> Option<T> {
+    let digits = value.strip_prefix('-').unwrap_or(value);
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    value.parse().ok()
+}
+
+/// The raw value of the last `name` parameter in `query`.
+fn query_value<'a>(query: &'a str, name: &str) -> Option<&'a str> {
+    query.split('&').rev().find_map(|item| {
+        let (key, value) = item.split_once('=')?;
+        (percent_decode_str(key).decode_utf8_lossy() == name).then_some(value)
+    })
+}
+
+/// Byte range of `part` in `input`, `part` must be a slice of `input`.
+fn span_of(input: &str, part: &str) -> Span {
+    let start = part.as_ptr() as usize - input.as_ptr() as usize;
+    start..start + part.len()
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum HmacHash {
     SHA1,
     SHA256,
+    SHA384,
     SHA512,
 }
 
-#[derive(Debug, PartialEq)]
-enum OtpType {
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum OtpType {
     HOTP,
     TOTP,
+    STEAM,
 }
 
+#[derive(Debug, PartialEq, Eq)]
 pub struct Account {
     protocol: String,
     otp_type: OtpType,
@@ -31,33 +411,233 @@ pub struct Account {
     parameters: Parameters, // queryString
 }
 
+#[derive(PartialEq, Eq)]
 pub struct Parameters {
     secret_key: String,
     issuer: String,
     hash_algorithm: HmacHash,
     code_digits: u8,
-    counter: i64,
-    step_period: u8,
+    counter: Option<i64>,
+    step_period: u32,
+    /// T0 of RFC 6238, the unix time TOTP timesteps are counted from.
+    start_time: i64,
+    encoding: OtpEncoding,
+    /// Parameters this crate doesn't use, e.g. `image`, kept for re-export.
+    extra: Vec<(String, String)>,
 }
 
 impl Account {
-    fn decode_label(label: &str) -> Result<(String, String), AccountError> {
-        let decoded_s = percent_decode_str(label).decode_utf8_lossy();
-        let (issuer, account_name) = match decoded_s.matches(':').count() {
-            0 => ("", label),
-            1 => decoded_s.split_once(':').unwrap_or(("", label)),
-            _ => {
-                return Err(AccountError::Parsing(
-                    "malformed input: invalid issuer field".to_string(),
-                ))
+    /// Splits a raw label into issuer and account name on its first colon,
+    /// literal or percent-encoded. Later colons are part of the account name.
+    fn split_label(label: &str) -> (&str, &str) {
+        for (i, byte) in label.bytes().enumerate() {
+            if byte == b':' {
+                return (&label[..i], &label[i + 1..]);
             }
+            if byte == b'%'
+                && label
+                    .get(i + 1..i + 3)
+                    .is_some_and(|hex| hex.eq_ignore_ascii_case("3a"))
+            {
+                return (&label[..i], &label[i + 3..]);
+            }
+        }
+        (&label[..0], label)
+    }
+
+    fn decode_label_part(part: &str) -> String {
+        percent_decode_str(part)
+            .decode_utf8_lossy()
+            .trim()
+            .to_string()
+    }
+
+    pub fn otp_type(&self) -> OtpType {
+        self.otp_type
+    }
+
+    /// Issuer prefix of the label, empty when the label has none.
+    pub fn label_issuer(&self) -> &str {
+        &self.label_issuer
+    }
+
+    pub fn account_name(&self) -> &str {
+        &self.label_account_name
+    }
+
+    pub fn parameters(&self) -> &Parameters {
+        &self.parameters
+    }
+
+    pub fn decoded_secret_key(&self) -> Result<Vec<u8>, AccountError> {
+        base32::decode(&self.parameters.secret_key).map_err(|err| AccountError::InvalidParameter {
+            name: "secret",
+            reason: err.to_string(),
+            span: None,
+        })
+    }
+
+    pub fn hex_encoded_secret_key(&self) -> Result<String, AccountError> {
+        Ok(hex::encode(self.decoded_secret_key()?))
+    }
+
+    /// Prepares a code generator from the account's secret and parameters.
+    pub fn generator(&self) -> Result<otp::OtpGenerator, AccountError> {
+        let params = &self.parameters;
+        Ok(otp::OtpGenerator::new(
+            &self.decoded_secret_key()?,
+            params.hash_algorithm,
+            params.code_digits,
+            params.step_period,
+        )?
+        .with_start_time(params.start_time)
+        .with_encoding(params.encoding.clone()))
+    }
+
+    /// Prepares a verifier accepting codes within `window` of the expected one.
+    pub fn verifier(&self, window: Window) -> Result<Verifier, AccountError> {
+        Ok(Verifier::new(self.generator()?, window))
+    }
+
+    /// Generates the code for the given unix time.
+    /// HOTP accounts ignore the time and use the stored counter.
+    pub fn code_at(&self, unix_time: i64) -> Result<String, AccountError> {
+        let generator = self.generator()?;
+        let code = match self.otp_type {
+            // Parsing and the builder make sure HOTP accounts have a counter
+            OtpType::HOTP => generator.hotp(self.parameters.counter.unwrap_or_default()),
+            OtpType::TOTP | OtpType::STEAM => generator.totp(unix_time),
         };
-        Ok((issuer.trim().to_string(), account_name.trim().to_string()))
+        Ok(code)
+    }
+
+    pub fn code_with_clock(&self, clock: &dyn Clock) -> Result<String, AccountError> {
+        self.code_at(clock.now())
+    }
+
+    pub fn current_code(&self) -> Result<String, AccountError> {
+        self.code_with_clock(&SystemClock)
+    }
+
+    /// Seconds left before the code for the given unix time expires.
+    /// HOTP codes do not expire, so `None` is returned for them.
+    pub fn remaining_seconds_at(&self, unix_time: i64) -> Result<Option<u32>, AccountError> {
+        match self.otp_type {
+            OtpType::HOTP => Ok(None),
+            OtpType::TOTP | OtpType::STEAM => {
+                Ok(Some(self.generator()?.remaining_seconds(unix_time)))
+            }
+        }
+    }
+
+    pub fn remaining_seconds_with_clock(
+        &self,
+        clock: &dyn Clock,
+    ) -> Result<Option<u32>, AccountError> {
+        self.remaining_seconds_at(clock.now())
     }
 
+    pub fn remaining_seconds(&self) -> Result<Option<u32>, AccountError> {
+        self.remaining_seconds_with_clock(&SystemClock)
+    }
+
+    /// Previous, current and next codes around the given unix time.
+    /// HOTP codes are not tied to time, so `None` is returned for them.
+    pub fn code_window_at(&self, unix_time: i64) -> Result<Option<CodeWindow>, AccountError> {
+        match self.otp_type {
+            OtpType::HOTP => Ok(None),
+            OtpType::TOTP | OtpType::STEAM => Ok(Some(self.generator()?.totp_window(unix_time))),
+        }
+    }
+
+    pub fn code_window_with_clock(
+        &self,
+        clock: &dyn Clock,
+    ) -> Result<Option<CodeWindow>, AccountError> {
+        self.code_window_at(clock.now())
+    }
+
+    pub fn code_window(&self) -> Result<Option<CodeWindow>, AccountError> {
+        self.code_window_with_clock(&SystemClock)
+    }
+
+    #[deprecated(note = "use `set_secret_key`, which checks the secret")]
     pub fn update_secret_key(&mut self, new_key: String) {
         self.parameters.secret_key = new_key;
     }
+
+    #[deprecated(note = "use `set_counter`, which refuses negative counters")]
+    pub fn update_counter(&mut self, new_counter: i64) {
+        self.parameters.counter = Some(new_counter);
+    }
+
+    /// The base32 encoded secret, without padding or separators.
+    pub fn set_secret_key(&mut self, secret_key: &str) -> Result<(), AccountError> {
+        check_secret_key(secret_key)?;
+        self.parameters.secret_key = secret_key.to_string();
+        Ok(())
+    }
+
+    /// Sets the label, its issuer must be empty or match the `issuer` parameter.
+    /// Both parts are trimmed, as they are when parsing.
+    pub fn set_label(&mut self, issuer: &str, account_name: &str) -> Result<(), AccountError> {
+        let issuer = issuer.trim();
+        if !issuer.is_empty() {
+            check_issuer(issuer)?;
+            if issuer != self.parameters.issuer {
+                return Err(AccountError::InvalidParameter {
+                    name: "label issuer",
+                    reason: format!("must match the issuer {:?}", self.parameters.issuer),
+                    span: None,
+                });
+            }
+        }
+        self.label_issuer = issuer.to_string();
+        self.label_account_name = account_name.trim().to_string();
+        Ok(())
+    }
+
+    /// Sets the `issuer` parameter, and the label issuer if the label has one.
+    /// The issuer is trimmed, as it is when parsing.
+    pub fn set_issuer(&mut self, issuer: &str) -> Result<(), AccountError> {
+        let issuer = issuer.trim();
+        check_issuer(issuer)?;
+        if !self.label_issuer.is_empty() {
+            self.label_issuer = issuer.to_string();
+        }
+        self.parameters.issuer = issuer.to_string();
+        Ok(())
+    }
+
+    pub fn set_hash_algorithm(&mut self, hash_algorithm: HmacHash) -> Result<(), AccountError> {
+        check_hash_algorithm(self.otp_type, hash_algorithm)?;
+        self.parameters.hash_algorithm = hash_algorithm;
+        Ok(())
+    }
+
+    pub fn set_code_digits(&mut self, code_digits: u8) -> Result<(), AccountError> {
+        check_code_digits(self.otp_type, code_digits)?;
+        self.parameters.code_digits = code_digits;
+        Ok(())
+    }
+
+    pub fn set_step_period(&mut self, step_period: u32) -> Result<(), AccountError> {
+        check_step_period(step_period)?;
+        self.parameters.step_period = step_period;
+        Ok(())
+    }
+
+    pub fn set_start_time(&mut self, start_time: i64) {
+        self.parameters.start_time = start_time;
+    }
+
+    /// Stores a new HOTP counter, e.g. the one returned by
+    /// [`Verifier::resync_hotp`].
+    pub fn set_counter(&mut self, counter: i64) -> Result<(), AccountError> {
+        check_counter(counter)?;
+        self.parameters.counter = Some(counter);
+        Ok(())
+    }
 }
 
 impl fmt::Display for HmacHash {
@@ -65,6 +645,7 @@ impl fmt::Display for HmacHash {
         let hash_algorithm = match self {
             Self::SHA1 => "SHA1",
             Self::SHA256 => "SHA256",
+            Self::SHA384 => "SHA384",
             Self::SHA512 => "SHA512",
         };
         write!(f, "{}", hash_algorithm)
@@ -76,6 +657,7 @@ impl fmt::Display for OtpType {
         let otp_type = match self {
             Self::HOTP => "hotp",
             Self::TOTP => "totp",
+            Self::STEAM => "steam",
         };
         write!(f, "{}", otp_type)
     }
@@ -83,144 +665,441 @@ impl fmt::Display for OtpType {
 
 impl fmt::Display for Account {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let mut params = self.parameters.to_string();
-        if self.otp_type == OtpType::TOTP {
-            let re = Regex::new(r"&counter=[\-]*[0-9]*").unwrap();
-            params = re.replace_all(&params, "").to_string();
-        } else {
-            let re = Regex::new(r"&period=[0-9]*").unwrap();
-            params = re.replace_all(&params, "").to_string();
+        // `steam` is only read, other apps know Steam accounts as totp with encoder=steam
+        let otp_type = match self.otp_type {
+            OtpType::STEAM => OtpType::TOTP,
+            otp_type => otp_type,
+        };
+        write!(f, "{}://{}/", self.protocol, otp_type)?;
+        // Without the prefix the first colon of the name would become the issuer
+        if !self.label_issuer.is_empty() || self.label_account_name.contains(':') {
+            write!(
+                f,
+                "{}:",
+                utf8_percent_encode(&self.label_issuer, NON_ALPHANUMERIC)
+            )?;
         }
-
-        let encoded_label_issuer =
-            utf8_percent_encode(&self.label_issuer, NON_ALPHANUMERIC).to_string();
-
         write!(
             f,
-            "{}://{}/{}:{}?{}",
-            self.protocol, self.otp_type, encoded_label_issuer, self.label_account_name, params,
-        )
+            "{}?",
+            utf8_percent_encode(&self.label_account_name, ACCOUNT_NAME_ENCODE_SET)
+        )?;
+        self.parameters.write_query(f, Some(&self.otp_type))
     }
 }
 
 impl fmt::Display for Parameters {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let encoded_issuer = utf8_percent_encode(&self.issuer, NON_ALPHANUMERIC).to_string();
-        write!(
-            f,
-            "secret={}&issuer={}&algorithm={}&digits={}&counter={}&period={}",
-            self.secret_key,
-            encoded_issuer,
-            self.hash_algorithm,
-            self.code_digits,
-            self.counter,
-            self.step_period
-        )
+        self.write_query(f, None)
     }
 }
 
 impl FromStr for Account {
     type Err = AccountError;
     fn from_str(s: &str) -> Result<Self, AccountError> {
+        Account::parse_with(s, ParseOptions::lenient()).map(|(account, _)| account)
+    }
+}
+
+impl Account {
+    /// Parses an otpauth URI, also returning the deviations a lenient
+    /// parse accepted.
+    pub fn parse_with(
+        s: &str,
+        options: ParseOptions,
+    ) -> Result<(Self, Vec<ParseWarning>), AccountError> {
         let (uri, query) = s
             .split_once('?')
-            .ok_or_else(|| AccountError::Parsing(format!("missing uri parameters:\n{s}")))?;
-
-        let params: Parameters = match Parameters::from_str(query) {
-            Ok(x) => x,
-            Err(err) => return Err(AccountError::Parsing(format!("{err}:\n{s}"))),
-        };
+            .ok_or(AccountError::MissingQuery { span: 0..s.len() })?;
 
         let (protocol, uri) = uri
             .split_once("://")
-            .ok_or_else(|| AccountError::Parsing(format!("missing protocol:\n{s}")))?;
+            .ok_or_else(|| AccountError::MissingScheme {
+                span: span_of(s, uri),
+            })?;
 
         if protocol != "otpauth" {
-            return Err(AccountError::Parsing(format!("wrong protocol:\n{s}")));
+            return Err(AccountError::WrongScheme {
+                found: protocol.to_string(),
+                span: span_of(s, protocol),
+            });
         }
 
         let (otp_type, label) = uri
             .split_once('/')
-            .ok_or_else(|| AccountError::Parsing(format!("missing otp type or label:\n{s}")))?;
+            .ok_or_else(|| AccountError::MissingLabel {
+                span: span_of(s, uri),
+            })?;
+
+        let mut diagnostics = Diagnostics::new(options);
+        let mut params = Parameters::parse(s, query, &mut diagnostics)?;
 
+        // A custom alphabet equal to Steam's is just an alphabet
+        let steam_encoder = query_value(query, "encoder")
+            .is_some_and(|value| percent_decode_str(value).decode_utf8_lossy() == "steam");
         let otp_type = match otp_type {
             "hotp" => OtpType::HOTP,
+            "totp" if steam_encoder => OtpType::STEAM,
             "totp" => OtpType::TOTP,
-            _ => return Err(AccountError::Parsing(format!("wrong otp type:\n{s}"))),
+            "steam" => OtpType::STEAM,
+            _ => {
+                return Err(AccountError::UnknownOtpType {
+                    found: otp_type.to_string(),
+                    span: span_of(s, otp_type),
+                })
+            }
         };
 
-        if otp_type == OtpType::HOTP && params.counter == -1 {
-            return Err(AccountError::Parsing(format!("missing hotp counter:\n{s}")));
+        if otp_type == OtpType::STEAM {
+            // Steam Guard codes are always 5 characters of SHA1 TOTP every 30 seconds
+            let differs = [
+                ("digits", params.code_digits != otp::STEAM_DIGITS),
+                ("algorithm", params.hash_algorithm != HmacHash::SHA1),
+                ("period", params.step_period != otp::STEAM_PERIOD),
+                ("encoder", params.encoding != OtpEncoding::steam()),
+            ];
+            for (name, differs) in differs {
+                if let Some(value) = query_value(query, name).filter(|_| differs) {
+                    diagnostics.report(ParseWarning::SteamParameter {
+                        name,
+                        value: value.to_string(),
+                        span: span_of(s, value),
+                    })?;
+                }
+            }
+            params.code_digits = otp::STEAM_DIGITS;
+            params.hash_algorithm = HmacHash::SHA1;
+            params.step_period = otp::STEAM_PERIOD;
+            params.encoding = OtpEncoding::steam();
         }
 
-        let (label_issuer, label_account_name) = match Account::decode_label(label) {
-            Ok((x, y)) => (x, y),
-            Err(err) => return Err(AccountError::Parsing(format!("{err}:\n{s}"))),
-        };
+        if otp_type == OtpType::HOTP && params.counter.is_none() {
+            return Err(AccountError::MissingCounter {
+                span: span_of(s, query),
+            });
+        }
 
-        Ok(Account {
+        let (raw_issuer, raw_account_name) = Account::split_label(label);
+        let label_issuer = Account::decode_label_part(raw_issuer);
+        let label_account_name = Account::decode_label_part(raw_account_name);
+        if params.issuer.is_empty() {
+            // Only reachable when lenient, the label prefix is the issuer then
+            params.issuer = label_issuer.clone();
+        } else if !label_issuer.is_empty() && label_issuer != params.issuer {
+            diagnostics.report(ParseWarning::IssuerMismatch {
+                label_issuer: label_issuer.clone(),
+                issuer: params.issuer.clone(),
+                span: span_of(s, raw_issuer),
+            })?;
+        }
+
+        let account = Account {
             protocol: protocol.to_string(),
             otp_type,
             label_issuer,
             label_account_name,
             parameters: params,
-        })
+        };
+        Ok((account, diagnostics.warnings))
+    }
+}
+
+impl Parameters {
+    /// The base32 encoded secret.
+    pub fn secret_key(&self) -> &str {
+        &self.secret_key
+    }
+
+    pub fn issuer(&self) -> &str {
+        &self.issuer
+    }
+
+    pub fn hash_algorithm(&self) -> HmacHash {
+        self.hash_algorithm
+    }
+
+    pub fn code_digits(&self) -> u8 {
+        self.code_digits
+    }
+
+    /// The HOTP counter, `None` when the URI had none.
+    pub fn counter(&self) -> Option<i64> {
+        self.counter
+    }
+
+    pub fn step_period(&self) -> u32 {
+        self.step_period
+    }
+
+    /// T0, 0 unless the URI has a `t0` parameter.
+    pub fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    pub fn encoding(&self) -> &OtpEncoding {
+        &self.encoding
+    }
+
+    /// Parameters this crate doesn't use, as decoded name and value pairs.
+    pub fn extra_parameters(&self) -> &[(String, String)] {
+        &self.extra
+    }
+}
+
+// Keeps the secret out of logs and test failures
+impl fmt::Debug for Parameters {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Parameters")
+            .field("secret_key", &"<redacted>")
+            .field("issuer", &self.issuer)
+            .field("hash_algorithm", &self.hash_algorithm)
+            .field("code_digits", &self.code_digits)
+            .field("counter", &self.counter)
+            .field("step_period", &self.step_period)
+            .field("start_time", &self.start_time)
+            .field("encoding", &self.encoding)
+            .field("extra", &self.extra)
+            .finish()
     }
 }
 
 impl FromStr for Parameters {
     type Err = AccountError;
     fn from_str(s: &str) -> Result<Self, AccountError> {
-        let params = s.split('&').collect::<Vec<_>>();
+        Parameters::parse(s, s, &mut Diagnostics::new(ParseOptions::lenient()))
+    }
+}
+
+impl Parameters {
+    /// Writes the parameters as a query string for an account of `otp_type`,
+    /// unknown parameters come last.
+    fn write_query(&self, f: &mut fmt::Formatter<'_>, otp_type: Option<&OtpType>) -> fmt::Result {
+        let mut pairs = vec![("secret", self.secret_key.clone())];
+        if !self.issuer.is_empty() {
+            pairs.push(("issuer", self.issuer.clone()));
+        }
+        pairs.push(("algorithm", self.hash_algorithm.to_string()));
+        pairs.push(("digits", self.code_digits.to_string()));
+        if let Some(counter) = self.counter {
+            pairs.push(("counter", counter.to_string()));
+        }
+        if otp_type != Some(&OtpType::HOTP) || self.step_period != DEFAULT_STEP_PERIOD {
+            pairs.push(("period", self.step_period.to_string()));
+        }
+        if self.start_time != 0 {
+            pairs.push(("t0", self.start_time.to_string()));
+        }
+        match &self.encoding {
+            OtpEncoding::Decimal => (),
+            OtpEncoding::Hex => pairs.push(("encoder", "hex".to_string())),
+            OtpEncoding::Base32 => pairs.push(("encoder", "base32".to_string())),
+            // encoder=steam makes a totp URI a steam account, other
+            // accounts write Steam's alphabet out as a custom one
+            OtpEncoding::Alphabet(_) if otp_type == Some(&OtpType::STEAM) => {
+                pairs.push(("encoder", "steam".to_string()))
+            }
+            OtpEncoding::Alphabet(alphabet) => {
+                pairs.push(("encoder", "custom".to_string()));
+                pairs.push(("alphabet", alphabet.as_str().to_string()));
+            }
+        }
+        pairs.extend(
+            self.extra
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.clone())),
+        );
+
+        for (i, (name, value)) in pairs.iter().enumerate() {
+            if i > 0 {
+                f.write_str("&")?;
+            }
+            write!(
+                f,
+                "{}={}",
+                utf8_percent_encode(name, QUERY_ENCODE_SET),
+                utf8_percent_encode(value, QUERY_ENCODE_SET)
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Parses the query string `query`, a slice of `input`.
+    /// Error spans are positions in `input`.
+    fn parse(
+        input: &str,
+        query: &str,
+        diagnostics: &mut Diagnostics,
+    ) -> Result<Self, AccountError> {
+        let invalid = |name: &'static str, value: &str| AccountError::InvalidParameter {
+            name,
+            reason: format!("{value:?} is not supported"),
+            span: Some(span_of(input, value)),
+        };
         let mut secret_key = "";
         let mut issuer = "";
         let mut hash_algorithm: HmacHash = HmacHash::SHA1;
         let mut code_digits: u8 = 6;
-        let mut counter: i64 = -1;
-        let mut step_period: u8 = 30;
-        for item in params {
-            let (key, value) = item.split_once('=').ok_or_else(|| {
-                AccountError::Parsing("please check the query parameters".to_string())
-            })?;
-            match key {
+        let mut counter = None;
+        let mut step_period = DEFAULT_STEP_PERIOD;
+        let mut start_time: i64 = 0;
+        let mut encoder = None;
+        let mut alphabet = None;
+        let mut extra = Vec::new();
+        let mut seen: Vec<String> = Vec::new();
+        for item in query.split('&') {
+            let (key, value) =
+                item.split_once('=')
+                    .ok_or_else(|| AccountError::MalformedQuery {
+                        found: item.to_string(),
+                        span: span_of(input, item),
+                    })?;
+            let name = percent_decode_str(key).decode_utf8_lossy().to_string();
+            if seen.contains(&name) {
+                diagnostics.report(ParseWarning::DuplicateParameter {
+                    name: name.clone(),
+                    span: span_of(input, item),
+                })?;
+            }
+            seen.push(name.clone());
+            match name.as_str() {
                 "secret" => secret_key = value,
                 "issuer" => issuer = value,
                 "algorithm" => {
-                    hash_algorithm = match value {
+                    let decoded = percent_decode_str(value).decode_utf8_lossy();
+                    let upper = decoded.to_ascii_uppercase();
+                    if upper != *decoded {
+                        diagnostics.report(ParseWarning::LowercaseAlgorithm {
+                            value: value.to_string(),
+                            span: span_of(input, value),
+                        })?;
+                    }
+                    hash_algorithm = match upper.as_str() {
+                        "SHA1" => HmacHash::SHA1,
                         "SHA256" => HmacHash::SHA256,
+                        "SHA384" => HmacHash::SHA384,
                         "SHA512" => HmacHash::SHA512,
-                        _ => HmacHash::SHA1,
+                        _ => return Err(invalid("algorithm", value)),
                     }
                 }
-                "digits" => code_digits = value.parse().unwrap_or(6),
-                "counter" => counter = value.parse().unwrap_or(-1),
-                "period" => step_period = value.parse().unwrap_or(30),
-                _ => (),
+                "digits" => {
+                    code_digits = match parse_decimal(value) {
+                        Some(x @ 1..=10) => x,
+                        _ => return Err(invalid("digits", value)),
+                    }
+                }
+                "counter" => {
+                    counter = match parse_decimal(value) {
+                        Some(x) if check_counter(x).is_ok() => Some(x),
+                        _ => return Err(invalid("counter", value)),
+                    }
+                }
+                "period" => {
+                    step_period = match parse_decimal(value) {
+                        Some(x) if x > 0 => x,
+                        _ => return Err(invalid("period", value)),
+                    }
+                }
+                "t0" => start_time = parse_decimal(value).ok_or_else(|| invalid("t0", value))?,
+                "encoder" => encoder = Some(value),
+                "alphabet" => alphabet = Some(value),
+                _ => {
+                    diagnostics.report(ParseWarning::UnknownParameter {
+                        name: name.clone(),
+                        span: span_of(input, item),
+                    })?;
+                    let value = percent_decode_str(value).decode_utf8_lossy();
+                    extra.push((name, value.to_string()));
+                }
             }
         }
-        if secret_key.is_empty() || issuer.is_empty() {
-            return Err(AccountError::Parsing(
-                "required fields are empty".to_string(),
-            ));
+        // Trimmed like the label issuer, so the two compare alike
+        let issuer = percent_decode_str(issuer)
+            .decode_utf8_lossy()
+            .trim()
+            .to_string();
+        if issuer.is_empty() {
+            diagnostics.report(ParseWarning::MissingIssuer {
+                span: span_of(input, query),
+            })?;
         }
+        let decoded_secret = percent_decode_str(secret_key).decode_utf8_lossy();
+        let normalized_secret: String = decoded_secret
+            .chars()
+            .filter(|c| !(c.is_whitespace() || *c == '-' || *c == '='))
+            .collect();
+        // Checked once normalised, `secret=%3D%3D` has no secret either
+        if normalized_secret.is_empty() {
+            return Err(AccountError::MissingSecret {
+                span: span_of(input, query),
+            });
+        }
+        // Percent-encoding alone is not a deviation
+        if normalized_secret != decoded_secret {
+            diagnostics.report(ParseWarning::UnnormalizedSecret {
+                value: secret_key.to_string(),
+                span: span_of(input, secret_key),
+            })?;
+        }
+        base32::decode(&normalized_secret).map_err(|err| AccountError::InvalidParameter {
+            name: "secret",
+            reason: err.to_string(),
+            span: Some(span_of(input, secret_key)),
+        })?;
 
-        let issuer = percent_decode_str(issuer).decode_utf8_lossy();
+        let encoding = match (encoder, alphabet) {
+            (None, None) => OtpEncoding::Decimal,
+            (None, Some(alphabet)) => return Err(invalid("alphabet", alphabet)),
+            (Some(encoder), alphabet) => {
+                match (&*percent_decode_str(encoder).decode_utf8_lossy(), alphabet) {
+                    ("decimal", None) => OtpEncoding::Decimal,
+                    ("hex", None) => OtpEncoding::Hex,
+                    ("base32", None) => OtpEncoding::Base32,
+                    ("steam", None) => OtpEncoding::steam(),
+                    ("custom", Some(alphabet)) => OtpEncoding::Alphabet(
+                        CodeAlphabet::new(&percent_decode_str(alphabet).decode_utf8_lossy())
+                            .map_err(|_| invalid("alphabet", alphabet))?,
+                    ),
+                    (_, None) => return Err(invalid("encoder", encoder)),
+                    (_, Some(alphabet)) => return Err(invalid("alphabet", alphabet)),
+                }
+            }
+        };
 
         Ok(Parameters {
-            secret_key: secret_key.to_string(),
-            issuer: issuer.to_string(),
+            secret_key: normalized_secret,
+            issuer,
             hash_algorithm,
             code_digits,
             counter,
             step_period,
+            start_time,
+            encoding,
+            extra,
         })
     }
 }
 
 #[cfg(test)]
 mod tests {
-    //TODO: Change panic tests to check specific errors
     use super::*;
+    use proptest::prelude::*;
+
+    fn strict(uri: &str) -> Result<Account, AccountError> {
+        Account::parse_with(uri, ParseOptions::strict()).map(|(account, _)| account)
+    }
+
+    /// Name and offending text of the `InvalidParameter` error for `uri`.
+    fn invalid_parameter(uri: &str) -> (&'static str, &str) {
+        match Account::from_str(uri) {
+            Err(AccountError::InvalidParameter {
+                name,
+                span: Some(span),
+                ..
+            }) => (name, &uri[span]),
+            Err(e) => panic!("unexpected error {e}"),
+            Ok(_) => panic!("{uri} parsed"),
+        }
+    }
 
     #[test]
     fn parse_uri_all_params() {
@@ -244,13 +1123,12 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
     fn parse_uri_missing_params() {
         let uri = "otpauth://totp/ACMECo:john.doe@email.com";
-        match Account::from_str(uri) {
-            Ok(x) => x,
-            Err(e) => panic!("{e}"),
-        };
+        assert!(matches!(
+            Account::from_str(uri),
+            Err(AccountError::MissingQuery { span }) if span == (0..uri.len())
+        ));
     }
 
     #[test]
@@ -301,36 +1179,662 @@ mod tests {
     }
 
     #[test]
-    #[should_panic]
+    fn parse_uri_label_colons() {
+        let base = "secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME";
+        for label in [
+            "ACME:alice:admin",
+            "ACME%3Aalice:admin",
+            "ACME%3aalice%3Aadmin",
+            "ACME:%20alice:admin",
+        ] {
+            let test_account =
+                Account::from_str(&format!("otpauth://totp/{label}?{base}")).unwrap();
+            assert_eq!(test_account.label_issuer, "ACME");
+            assert_eq!(test_account.label_account_name, "alice:admin");
+            assert!(test_account
+                .to_string()
+                .starts_with("otpauth://totp/ACME:alice%3Aadmin?"));
+        }
+
+        // An empty issuer keeps the colons in the name
+        let uri = format!("otpauth://totp/:alice:admin?{base}");
+        let test_account = Account::from_str(&uri).unwrap();
+        assert_eq!(test_account.label_issuer, "");
+        assert_eq!(test_account.label_account_name, "alice:admin");
+        assert!(test_account
+            .to_string()
+            .starts_with("otpauth://totp/:alice%3Aadmin?"));
+
+        let uri = format!("otpauth://totp/alice%20doe?{base}");
+        let test_account = Account::from_str(&uri).unwrap();
+        assert_eq!(test_account.label_account_name, "alice doe");
+    }
+
+    #[test]
+    fn parse_uri_issuer_mismatch() {
+        let uri = "otpauth://totp/Other%20Co:john.doe?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo";
+        let err = strict(uri).err().unwrap();
+        assert!(matches!(
+            &err,
+            AccountError::IssuerMismatch { label_issuer, issuer, .. }
+                if label_issuer == "Other Co" && issuer == "ACMECo"
+        ));
+        assert_eq!(&uri[err.span().unwrap()], "Other%20Co");
+
+        let (test_account, warnings) = Account::parse_with(uri, ParseOptions::lenient()).unwrap();
+        assert_eq!(test_account.parameters.issuer, "ACMECo");
+        assert!(matches!(
+            warnings.as_slice(),
+            [ParseWarning::IssuerMismatch { .. }]
+        ));
+
+        // Both issuers are trimmed before comparing
+        let uri = "otpauth://totp/ACME%20:john.doe?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACME%20";
+        let test_account = strict(uri).unwrap();
+        assert_eq!(test_account.label_issuer, "ACME");
+        assert_eq!(test_account.parameters.issuer, "ACME");
+        assert!(strict("otpauth://totp/ACME:john.doe?secret=GEZDGNBV&issuer=%20").is_err());
+    }
+
+    #[test]
     fn parse_uri_malformed_otp_type() {
         let uri = "otpauth://xotp/ACMECo:john.doe@email.com?\
                    secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXBOZ&issuer=ACMECo";
-        match Account::from_str(uri) {
-            Ok(x) => x,
-            Err(e) => panic!("{e}"),
-        };
+        assert!(matches!(
+            Account::from_str(uri),
+            Err(AccountError::UnknownOtpType { found, span }) if found == "xotp" && span == (10..14)
+        ));
     }
 
     #[test]
-    #[should_panic]
     fn parse_uri_malformed_protocol() {
         let uri = "https://totp/ACMECo:john.doe@email.com?\
                    secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXBOZ&issuer=ACMECo";
-        match Account::from_str(uri) {
+        assert!(matches!(
+            Account::from_str(uri),
+            Err(AccountError::WrongScheme { found, span }) if found == "https" && span == (0..5)
+        ));
+    }
+
+    #[test]
+    fn parse_uri_hotp_counter_missing() {
+        let uri = "otpauth://hotp/ACMECo:john.doe@email.com?\
+                   secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXBOZ&issuer=ACMECo";
+        assert!(matches!(
+            Account::from_str(uri),
+            Err(AccountError::MissingCounter { .. })
+        ));
+    }
+
+    #[test]
+    fn parse_uri_malformed_secret() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXB01&issuer=ACMECo";
+        assert_eq!(
+            invalid_parameter(uri),
+            ("secret", "HXDMVJECJJWSRB3HWIZR4IFUGFTMXB01")
+        );
+    }
+
+    #[test]
+    fn parse_uri_error_spans() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&digits=eight";
+        let err = Account::from_str(uri).err().unwrap();
+        assert_eq!(&uri[err.span().unwrap()], "eight");
+
+        let uri = "otpauth://totp/ACME:Co:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo";
+        let err = strict(uri).err().unwrap();
+        assert!(matches!(err, AccountError::IssuerMismatch { .. }));
+        assert_eq!(&uri[err.span().unwrap()], "ACME");
+
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?secret&issuer=ACMECo";
+        let err = Account::from_str(uri).err().unwrap();
+        assert!(matches!(err, AccountError::MalformedQuery { .. }));
+        assert_eq!(&uri[err.span().unwrap()], "secret");
+
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?issuer=ACMECo";
+        let err = Account::from_str(uri).err().unwrap();
+        assert!(matches!(err, AccountError::MissingSecret { .. }));
+        assert_eq!(&uri[err.span().unwrap()], "issuer=ACMECo");
+
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?secret=GEZDGNBV";
+        assert!(matches!(
+            strict(uri),
+            Err(AccountError::MissingIssuer { .. })
+        ));
+        assert!(matches!(
+            Account::from_str("totp/ACMECo:john.doe?secret=GEZDGNBV&issuer=ACMECo"),
+            Err(AccountError::MissingScheme { .. })
+        ));
+        assert!(matches!(
+            Account::from_str("otpauth://totp?secret=GEZDGNBV&issuer=ACMECo"),
+            Err(AccountError::MissingLabel { .. })
+        ));
+        for counter in ["x", "-1", "-5", "+5", ""] {
+            let uri = format!(
+                "otpauth://hotp/ACMECo:john.doe?secret=GEZDGNBV&issuer=ACMECo&counter={counter}"
+            );
+            assert_eq!(invalid_parameter(&uri), ("counter", counter));
+        }
+        let uri = "otpauth://totp/ACMECo:john.doe?secret=GEZDGNBV&issuer=ACMECo&t0=+30";
+        assert_eq!(invalid_parameter(uri), ("t0", "+30"));
+    }
+
+    #[test]
+    fn parse_uri_lenient() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZD%20GNBV-GY3TQOJQGEZDGNBVGY3TQOJQ====&algorithm=sha256\
+                   &image=https%3A%2F%2Facme.co%2Flogo.png&digits=6&digits=8";
+        let (test_account, warnings) = Account::parse_with(uri, ParseOptions::lenient()).unwrap();
+        assert_eq!(
+            test_account.parameters.secret_key,
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+        );
+        assert_eq!(test_account.parameters.issuer, "ACMECo");
+        assert_eq!(test_account.parameters.hash_algorithm, HmacHash::SHA256);
+        assert_eq!(test_account.parameters.code_digits, 8);
+        assert_eq!(warnings.len(), 5);
+        assert_eq!(&uri[warnings[0].span()], "sha256");
+        assert!(matches!(
+            &warnings[1],
+            ParseWarning::UnknownParameter { name, .. } if name == "image"
+        ));
+        assert_eq!(&uri[warnings[2].span()], "digits=8");
+        assert!(matches!(&warnings[3], ParseWarning::MissingIssuer { .. }));
+        assert_eq!(warnings[3].to_string(), "missing issuer parameter");
+        assert!(matches!(
+            &warnings[4],
+            ParseWarning::UnnormalizedSecret { .. }
+        ));
+
+        // Nothing is left of these secrets once normalised
+        for secret in ["", "%20", "%3D%3D", "-"] {
+            let uri = format!("otpauth://totp/ACMECo:john.doe?secret={secret}&issuer=ACMECo");
+            assert!(matches!(
+                Account::parse_with(&uri, ParseOptions::lenient()),
+                Err(AccountError::MissingSecret { .. })
+            ));
+        }
+
+        // A conforming URI has no warnings
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo";
+        let (_, warnings) = Account::parse_with(uri, ParseOptions::lenient()).unwrap();
+        assert!(warnings.is_empty());
+    }
+
+    #[test]
+    fn parse_uri_strict() {
+        let base = "otpauth://totp/ACMECo:john.doe@email.com?\
+                    secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo";
+        assert!(matches!(
+            strict(&format!("{base}&algorithm=sha256")),
+            Err(AccountError::InvalidParameter { name, .. }) if name == "algorithm"
+        ));
+        assert!(matches!(
+            strict(&format!("{base}&image=logo.png")),
+            Err(AccountError::UnknownParameter { name, .. }) if name == "image"
+        ));
+        assert!(matches!(
+            strict(&format!("{base}&digits=6&digits=8")),
+            Err(AccountError::DuplicateParameter { name, .. }) if name == "digits"
+        ));
+        assert!(matches!(
+            strict(
+                "otpauth://totp/ACMECo:john.doe@email.com?secret=MZXW6YQ=&issuer=ACMECo"
+            ),
+            Err(AccountError::InvalidParameter { name, .. }) if name == "secret"
+        ));
+        // Bad values are errors whatever the mode
+        assert!(matches!(
+            Account::parse_with(&format!("{base}&digits=11"), ParseOptions::lenient()),
+            Err(AccountError::InvalidParameter { name, .. }) if name == "digits"
+        ));
+        assert!(strict(base).is_ok());
+        let encoded = strict(&base.replace("OJQ&", "OJ%51&")).unwrap();
+        assert_eq!(
+            encoded.parameters.secret_key,
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ"
+        );
+        assert!(!ParseOptions::default().is_lenient());
+        // from_str accepts what the strict parse refuses
+        assert!(Account::from_str(&format!("{base}&algorithm=sha256&digits=6&digits=8")).is_ok());
+        assert!(Account::from_str("otpauth://totp/ACMECo:john.doe?secret=GEZDGNBV").is_ok());
+    }
+
+    #[test]
+    fn decode_secret_key() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=gezdgnbvgy3tqojqgezdgnbvgy3tqojq&issuer=ACMECo";
+        let test_account = match Account::from_str(uri) {
             Ok(x) => x,
             Err(e) => panic!("{e}"),
         };
+        assert_eq!(
+            test_account.decoded_secret_key().unwrap(),
+            b"12345678901234567890"
+        );
+        assert_eq!(
+            test_account.hex_encoded_secret_key().unwrap(),
+            "3132333435363738393031323334353637383930"
+        );
     }
 
     #[test]
-    #[should_panic]
-    fn parse_uri_hotp_counter_missing() {
+    fn parsed_account_computes_rfc_totp() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&digits=8";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        let key = test_account.hex_encoded_secret_key().unwrap();
+        let params = &test_account.parameters;
+        assert_eq!(
+            "94287082",
+            otp::compute_totp(
+                &key,
+                59,
+                params.code_digits,
+                params.hash_algorithm,
+                params.step_period
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn totp_account_code_at() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&digits=8";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.code_at(59).unwrap(), "94287082");
+        assert_eq!(test_account.code_at(1111111109).unwrap(), "07081804");
+        assert_eq!(test_account.remaining_seconds_at(59).unwrap(), Some(1));
+        assert_eq!(test_account.remaining_seconds_at(60).unwrap(), Some(30));
+        assert_eq!(test_account.current_code().unwrap().len(), 8);
+
+        let clock = crate::token::clock::FixedClock::new(1234567890);
+        assert_eq!(test_account.code_with_clock(&clock).unwrap(), "89005924");
+        assert_eq!(
+            test_account.remaining_seconds_with_clock(&clock).unwrap(),
+            Some(30)
+        );
+    }
+
+    #[test]
+    fn totp_account_start_time() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&digits=8&t0=-30";
+        let test_account = Account::from_str(uri).unwrap();
+        assert_eq!(test_account.parameters().start_time(), -30);
+        // Timestep 1 now starts at unix time 0
+        assert_eq!(test_account.code_at(0).unwrap(), "94287082");
+        assert_eq!(test_account.remaining_seconds_at(0).unwrap(), Some(30));
+        assert_eq!(test_account.remaining_seconds_at(29).unwrap(), Some(1));
+        assert_eq!(
+            test_account.generator().unwrap().remaining_seconds(29),
+            test_account.remaining_seconds_at(29).unwrap().unwrap()
+        );
+        assert!(test_account.to_string().ends_with("&period=30&t0=-30"));
+        assert!(Account::from_str("otpauth://totp/A:b?secret=GEZDGNBV&issuer=A&t0=x").is_err());
+    }
+
+    #[test]
+    fn account_code_window() {
+        let uri = "otpauth://steam/Steam:john.doe?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Steam";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        let clock = crate::token::clock::FixedClock::new(1111111109);
+        let window = test_account
+            .code_window_with_clock(&clock)
+            .unwrap()
+            .unwrap();
+        assert_eq!(window.current.code, "PY4YB");
+        assert_eq!(window.current.valid_until, 1111111110);
+        assert_eq!(window.next.code, test_account.code_at(1111111110).unwrap());
+        assert_eq!(
+            window.previous.code,
+            test_account.code_at(1111111079).unwrap()
+        );
+
         let uri = "otpauth://hotp/ACMECo:john.doe@email.com?\
-                   secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXBOZ&issuer=ACMECo";
-        match Account::from_str(uri) {
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&counter=1";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.code_window().unwrap(), None);
+    }
+
+    #[test]
+    fn hotp_account_code_at() {
+        let uri = "otpauth://hotp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&counter=1";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.code_at(59).unwrap(), "287082");
+        assert_eq!(test_account.current_code().unwrap(), "287082");
+        assert_eq!(test_account.remaining_seconds().unwrap(), None);
+    }
+
+    #[test]
+    fn account_verifier() {
+        let uri = "otpauth://hotp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&counter=1";
+        let test_account = match Account::from_str(uri) {
             Ok(x) => x,
             Err(e) => panic!("{e}"),
         };
+        let mut verifier = test_account.verifier(Window::new(0, 2)).unwrap();
+        assert_eq!(
+            verifier.verify_hotp("969429", test_account.parameters.counter.unwrap()),
+            Some(3)
+        );
+    }
+
+    #[test]
+    fn account_hotp_resync() {
+        let uri = "otpauth://hotp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&counter=0";
+        let mut test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        let mut verifier = test_account.verifier(Window::new(0, 1)).unwrap();
+        let new_counter = verifier
+            .resync_hotp(
+                &["162583", "399871"],
+                test_account.parameters.counter.unwrap(),
+                50,
+            )
+            .unwrap()
+            .unwrap();
+        test_account.set_counter(new_counter).unwrap();
+        assert_eq!(test_account.current_code().unwrap(), "520489");
+    }
+
+    #[test]
+    fn account_setters() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo";
+        let mut test_account = Account::from_str(uri).unwrap();
+        assert_eq!(test_account.parameters().counter(), None);
+        assert!(!format!("{test_account:?}").contains("GEZDGNBV"));
+
+        test_account.set_issuer(" ACME Co").unwrap();
+        assert_eq!(test_account.label_issuer(), "ACME Co");
+        assert_eq!(test_account.parameters().issuer(), "ACME Co");
+        test_account.set_label("", " jane:doe").unwrap();
+        assert_eq!(test_account.account_name(), "jane:doe");
+        test_account.set_hash_algorithm(HmacHash::SHA512).unwrap();
+        test_account.set_code_digits(8).unwrap();
+        test_account.set_step_period(60).unwrap();
+        test_account.set_counter(7).unwrap();
+        assert_eq!(
+            test_account.to_string(),
+            "otpauth://totp/:jane%3Adoe?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ\
+             &issuer=ACME%20Co&algorithm=SHA512&digits=8&counter=7&period=60"
+        );
+        assert!(strict(&test_account.to_string()).unwrap() == test_account);
+        test_account.set_label(" ACME Co ", "jane").unwrap();
+        test_account.set_secret_key("MZXW6YTBOI").unwrap();
+        assert!(strict(&test_account.to_string()).unwrap() == test_account);
+
+        assert!(test_account.set_label("Other Co", "jane").is_err());
+        assert!(test_account.set_issuer("").is_err());
+        assert!(test_account.set_issuer("AC:ME").is_err());
+        assert!(test_account.set_code_digits(11).is_err());
+        assert!(test_account.set_step_period(0).is_err());
+        assert!(test_account.set_counter(-1).is_err());
+        assert!(test_account.set_secret_key("MZXW 6YTBOI").is_err());
+        assert!(test_account.set_secret_key("MZXW6YTBO1").is_err());
+        assert_eq!(test_account.parameters().code_digits(), 8);
+        assert_eq!(test_account.parameters().secret_key(), "MZXW6YTBOI");
+
+        let uri = "otpauth://steam/Steam:john.doe?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Steam";
+        let mut test_account = Account::from_str(uri).unwrap();
+        assert_eq!(test_account.otp_type(), OtpType::STEAM);
+        assert!(test_account.set_code_digits(6).is_err());
+        assert!(test_account.set_hash_algorithm(HmacHash::SHA256).is_err());
+    }
+
+    #[test]
+    fn parse_uri_sha384() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXBOZ&issuer=ACMECo&algorithm=SHA384";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.parameters.hash_algorithm, HmacHash::SHA384);
+        assert!(test_account.to_string().contains("&algorithm=SHA384&"));
+    }
+
+    #[test]
+    fn parse_uri_unknown_algorithm() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXBOZ&issuer=ACMECo&algorithm=MD5";
+        assert_eq!(invalid_parameter(uri), ("algorithm", "MD5"));
+    }
+
+    #[test]
+    fn parse_uri_steam() {
+        let uri = "otpauth://steam/Steam:john.doe?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Steam";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.otp_type, OtpType::STEAM);
+        assert_eq!(test_account.parameters.code_digits, 5);
+        assert_eq!(test_account.code_at(59).unwrap(), "PV9M4");
+        assert_eq!(test_account.remaining_seconds_at(59).unwrap(), Some(1));
+        assert_eq!(
+            test_account.to_string(),
+            "otpauth://totp/Steam:john.doe?\
+             secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Steam&algorithm=SHA1&digits=5&period=30\
+             &encoder=steam"
+        );
+        // otpauth://steam/ is read as an alias only
+        let reparsed = strict(&test_account.to_string()).unwrap();
+        assert_eq!(reparsed.otp_type, OtpType::STEAM);
+        assert_eq!(reparsed, test_account);
+    }
+
+    #[test]
+    fn parse_uri_steam_parameters() {
+        let base = "otpauth://steam/Steam:john.doe?\
+                    secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Steam";
+        // Steam's own values are fine
+        let uri = format!("{base}&algorithm=SHA1&digits=5&period=30&encoder=steam");
+        assert!(Account::parse_with(&uri, ParseOptions::strict()).is_ok());
+
+        let uri = format!("{base}&algorithm=SHA256&digits=6&period=60&encoder=hex");
+        let err = Account::parse_with(&uri, ParseOptions::strict())
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err,
+            AccountError::InvalidParameter { name: "digits", .. }
+        ));
+        assert_eq!(&uri[err.span().unwrap()], "6");
+
+        let (test_account, warnings) = Account::parse_with(&uri, ParseOptions::lenient()).unwrap();
+        let names: Vec<_> = warnings
+            .iter()
+            .map(|w| match w {
+                ParseWarning::SteamParameter { name, .. } => *name,
+                _ => panic!("unexpected warning {w}"),
+            })
+            .collect();
+        assert_eq!(names, ["digits", "algorithm", "period", "encoder"]);
+        assert_eq!(test_account.parameters.code_digits, 5);
+        assert_eq!(test_account.parameters.hash_algorithm, HmacHash::SHA1);
+        assert_eq!(test_account.parameters.step_period, 30);
+        assert_eq!(test_account.parameters.encoding, OtpEncoding::steam());
+        assert_eq!(test_account.code_at(59).unwrap(), "PV9M4");
+    }
+
+    #[test]
+    fn parse_uri_steam_encoder() {
+        let uri = "otpauth://totp/Steam:john.doe?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Steam&encoder=steam";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.otp_type, OtpType::STEAM);
+        assert_eq!(test_account.code_at(1111111109).unwrap(), "PY4YB");
+    }
+
+    #[test]
+    fn parse_uri_unknown_encoder() {
+        let uri = "otpauth://totp/Steam:john.doe?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Steam&encoder=blizzard";
+        assert_eq!(invalid_parameter(uri), ("encoder", "blizzard"));
+    }
+
+    #[test]
+    fn parse_uri_encoders() {
+        let uri = "otpauth://hotp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&counter=0&encoder=hex";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.parameters.encoding, OtpEncoding::Hex);
+        assert_eq!(test_account.code_at(0).unwrap(), "93cf18");
+        assert!(test_account.to_string().ends_with("&counter=0&encoder=hex"));
+
+        let uri = "otpauth://hotp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&counter=0&digits=7&encoder=base32";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.code_at(0).unwrap(), "BGJHTYY");
+
+        // Values are percent-decoded like any other
+        let uri = "otpauth://totp/Steam:john.doe?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=Steam&algorithm=SHA%31&encoder=%73team";
+        let test_account = strict(uri).unwrap();
+        assert_eq!(test_account.otp_type, OtpType::STEAM);
+        assert_eq!(test_account.code_at(59).unwrap(), "PV9M4");
+        let uri = "otpauth://totp/ACMECo:john.doe?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&algorithm=SHA%32%35%36\
+                   &encoder=%63ustom&alphabet=%2A%23";
+        let test_account = strict(uri).unwrap();
+        assert_eq!(test_account.parameters.hash_algorithm, HmacHash::SHA256);
+        assert!(matches!(
+            test_account.parameters.encoding,
+            OtpEncoding::Alphabet(_)
+        ));
+    }
+
+    #[test]
+    fn parse_uri_custom_alphabet() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&digits=5\
+                   &encoder=custom&alphabet=23456789BCDFGHJKMNPQRTVWXY";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        // Only encoder=steam makes a steam account
+        assert_eq!(test_account.otp_type, OtpType::TOTP);
+        assert_eq!(test_account.parameters.encoding, OtpEncoding::steam());
+        assert_eq!(test_account.code_at(59).unwrap(), "PV9M4");
+        assert!(test_account
+            .to_string()
+            .starts_with("otpauth://totp/ACMECo:john.doe@email.com?"));
+        assert!(test_account
+            .to_string()
+            .ends_with("&encoder=custom&alphabet=23456789BCDFGHJKMNPQRTVWXY"));
+        assert!(Account::from_str(&test_account.to_string()).unwrap() == test_account);
+
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo\
+                   &encoder=custom&alphabet=%2A%23";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.code_at(59).unwrap().len(), 6);
+        assert!(test_account
+            .to_string()
+            .ends_with("&encoder=custom&alphabet=%2A%23"));
+    }
+
+    #[test]
+    fn parse_uri_invalid_alphabet() {
+        let base = "otpauth://totp/ACMECo:john.doe@email.com?\
+                    secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo";
+        assert!(Account::from_str(&format!("{base}&encoder=custom")).is_err());
+        assert!(Account::from_str(&format!("{base}&encoder=custom&alphabet=AA")).is_err());
+        assert!(Account::from_str(&format!("{base}&encoder=hex&alphabet=AB")).is_err());
+    }
+
+    #[test]
+    fn parse_uri_ten_digits() {
+        let uri = "otpauth://hotp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&counter=0&digits=10";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.parameters.code_digits, 10);
+        assert_eq!(test_account.code_at(0).unwrap(), "1284755224");
+    }
+
+    #[test]
+    fn parse_uri_invalid_digits() {
+        let base = "otpauth://totp/ACMECo:john.doe@email.com?\
+                    secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo";
+        for digits in ["0", "11", "255", "256", "-6", "+6", " 6", "６", "six", ""] {
+            assert!(Account::from_str(&format!("{base}&digits={digits}")).is_err());
+        }
+    }
+
+    #[test]
+    fn parse_uri_long_period() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&period=3600";
+        let test_account = match Account::from_str(uri) {
+            Ok(x) => x,
+            Err(e) => panic!("{e}"),
+        };
+        assert_eq!(test_account.parameters.step_period, 3600);
+        assert_eq!(test_account.remaining_seconds_at(7200).unwrap(), Some(3600));
+        assert_eq!(test_account.code_at(3600).unwrap(), "287082");
+    }
+
+    #[test]
+    fn parse_uri_zero_period() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&period=0";
+        assert_eq!(invalid_parameter(uri), ("period", "0"));
+    }
+
+    #[test]
+    fn parse_uri_malformed_period() {
+        let uri = "otpauth://totp/ACMECo:john.doe@email.com?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&period=-30";
+        assert_eq!(invalid_parameter(uri), ("period", "-30"));
+        assert!(Account::from_str(&uri.replace("-30", "+30")).is_err());
     }
 
     #[test]
@@ -362,4 +1866,115 @@ mod tests {
              secret=HXDMVJECJJWSRB3HWIZR4IFUGFTMXBOZ&issuer=ACME%20Co&algorithm=SHA1&digits=6&counter=300"
         );
     }
+
+    #[test]
+    fn account_to_string_is_lossless() {
+        // No label issuer, unknown parameters and a counter on a TOTP account
+        let uri = "otpauth://totp/john.doe%20jr?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=A%26B\
+                   &image=https%3A%2F%2Facme.co%2Flogo.png&lock=true&counter=5";
+        let (test_account, _) = Account::parse_with(uri, ParseOptions::lenient()).unwrap();
+        assert_eq!(
+            test_account.to_string(),
+            "otpauth://totp/john.doe%20jr?secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=A%26B\
+             &algorithm=SHA1&digits=6&counter=5&period=30\
+             &image=https%3A%2F%2Facme.co%2Flogo.png&lock=true"
+        );
+
+        let uri = "otpauth://hotp/ACMECo:john.doe?\
+                   secret=GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ&issuer=ACMECo&counter=1&period=60";
+        let test_account = Account::from_str(uri).unwrap();
+        assert!(test_account.to_string().ends_with("&counter=1&period=60"));
+    }
+
+    fn percent_encode(s: &str) -> String {
+        utf8_percent_encode(s, NON_ALPHANUMERIC).to_string()
+    }
+
+    prop_compose! {
+        fn arb_uri()(
+            otp_type in prop::sample::select(vec!["totp", "hotp", "steam"]),
+            label_issuer in prop::option::of("[^:\\s][^:]{0,10}"),
+            account_name in "\\PC{0,16}",
+            secret in prop::collection::vec(any::<u8>(), 16..40),
+            lowercase_secret in any::<bool>(),
+            issuer in prop::option::of("\\PC{0,10}"),
+            algorithm in prop::option::of(prop::sample::select(vec!["SHA1", "SHA256", "sha384", "Sha512"])),
+            digits in prop::option::of(1u8..=10),
+            counter in prop::option::of(0..i64::MAX),
+            period in prop::option::of(1..=u32::MAX),
+            encoding in prop::sample::select(vec![
+                "",
+                "&encoder=hex",
+                "&encoder=base32",
+                "&encoder=steam",
+                "&encoder=custom&alphabet=%2A%23",
+                "&encoder=custom&alphabet=23456789BCDFGHJKMNPQRTVWXY",
+            ]),
+            extra_names in prop::sample::subsequence(vec!["image", "color", "lock"], 0..=3),
+            extra_values in prop::collection::vec("\\PC{0,12}", 3),
+        ) -> String {
+            let mut secret = base32::encode(&secret);
+            if lowercase_secret {
+                secret = secret.to_lowercase();
+            }
+            let label = match label_issuer {
+                Some(issuer) => format!("{}:{}", percent_encode(&issuer), percent_encode(&account_name)),
+                None => percent_encode(&account_name),
+            };
+            let mut uri = format!("otpauth://{otp_type}/{label}?secret={secret}");
+            if let Some(issuer) = issuer {
+                uri += &format!("&issuer={}", percent_encode(&issuer));
+            }
+            if let Some(algorithm) = algorithm {
+                uri += &format!("&algorithm={algorithm}");
+            }
+            if let Some(digits) = digits {
+                uri += &format!("&digits={digits}");
+            }
+            match (otp_type, counter) {
+                (_, Some(counter)) => uri += &format!("&counter={counter}"),
+                ("hotp", None) => uri += "&counter=0",
+                _ => (),
+            }
+            if let Some(period) = period {
+                uri += &format!("&period={period}");
+            }
+            uri += encoding;
+            for (name, value) in extra_names.into_iter().zip(extra_values) {
+                uri += &format!("&{name}={}", percent_encode(&value));
+            }
+            uri
+        }
+    }
+
+    proptest! {
+        #[test]
+        fn account_display_round_trips(uri in arb_uri()) {
+            let (account, _) = Account::parse_with(&uri, ParseOptions::lenient()).unwrap();
+            let shown = account.to_string();
+            let (reparsed, warnings) = Account::parse_with(&shown, ParseOptions::lenient()).unwrap();
+            prop_assert!(reparsed == account, "{} was shown as {}", uri, shown);
+            prop_assert_eq!(reparsed.to_string(), shown.clone());
+            // Only what the crate can't interpret or fill in is left for a lenient parse
+            let has_issuer = !account.parameters.issuer.is_empty();
+            let mismatch = !account.label_issuer.is_empty()
+                && account.label_issuer != account.parameters.issuer;
+            prop_assert!(
+                warnings.iter().all(|w| match w {
+                    ParseWarning::UnknownParameter { .. } | ParseWarning::DuplicateParameter { .. } => true,
+                    ParseWarning::MissingIssuer { .. } => !has_issuer,
+                    ParseWarning::IssuerMismatch { .. } => mismatch,
+                    _ => false,
+                }),
+                "unexpected warnings {:?}",
+                warnings
+            );
+            prop_assert!(Account::from_str(&shown).unwrap() == account, "{} was shown as {}", uri, shown);
+            // Unknown parameters are kept by from_str, strict parsing refuses them
+            if has_issuer && !mismatch && account.parameters.extra.is_empty() {
+                prop_assert!(strict(&shown).is_ok(), "{} was shown as {}", uri, shown);
+            }
+        }
+    }
 }
@@ -0,0 +1,118 @@
+use crate::models::AccountError;
+use crate::token::clock::{Clock, SystemClock};
+use crate::token::motp;
+use crate::token::verify::Window;
+
+/// A Mobile-OTP account.
+/// The PIN is kept apart from the secret, it is only mixed in
+/// when a code is generated or verified.
+pub struct MotpAccount {
+    name: String,
+    secret: String,
+    pin: String,
+}
+
+fn check_pin(pin: &str) -> Result<(), AccountError> {
+    if pin.is_empty() || !pin.chars().all(|c| c.is_ascii_digit()) {
+        return Err(AccountError::InvalidParameter {
+            name: "pin",
+            reason: "must be numeric".to_string(),
+            span: None,
+        });
+    }
+    Ok(())
+}
+
+impl MotpAccount {
+    pub fn new(name: &str, secret: &str, pin: &str) -> Result<Self, AccountError> {
+        if secret.is_empty() {
+            return Err(AccountError::InvalidParameter {
+                name: "secret",
+                reason: "is empty".to_string(),
+                span: None,
+            });
+        }
+        check_pin(pin)?;
+        Ok(MotpAccount {
+            name: name.to_string(),
+            secret: secret.to_string(),
+            pin: pin.to_string(),
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn update_pin(&mut self, new_pin: &str) -> Result<(), AccountError> {
+        check_pin(new_pin)?;
+        self.pin = new_pin.to_string();
+        Ok(())
+    }
+
+    pub fn code_at(&self, unix_time: i64) -> Result<String, AccountError> {
+        Ok(motp::compute_motp(&self.secret, &self.pin, unix_time)?)
+    }
+
+    pub fn code_with_clock(&self, clock: &dyn Clock) -> Result<String, AccountError> {
+        self.code_at(clock.now())
+    }
+
+    pub fn current_code(&self) -> Result<String, AccountError> {
+        self.code_with_clock(&SystemClock)
+    }
+
+    /// Returns the 10 second timestep the code matched.
+    pub fn verify(
+        &self,
+        code: &str,
+        unix_time: i64,
+        window: Window,
+    ) -> Result<Option<i64>, AccountError> {
+        Ok(motp::verify_motp(
+            &self.secret,
+            &self.pin,
+            code,
+            unix_time,
+            window,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::token::clock::FixedClock;
+
+    #[test]
+    fn motp_account_codes() {
+        let account = MotpAccount::new("vpn", "0123456789abcdef", "1234").unwrap();
+        assert_eq!(account.name(), "vpn");
+        assert_eq!(account.code_at(59).unwrap(), "3982c0");
+        assert_eq!(
+            account
+                .code_with_clock(&FixedClock::new(1234567890))
+                .unwrap(),
+            "f41e13"
+        );
+        assert_eq!(
+            account.verify("3982c0", 69, Window::new(1, 0)).unwrap(),
+            Some(5)
+        );
+    }
+
+    #[test]
+    fn motp_account_pin_change() {
+        let mut account = MotpAccount::new("vpn", "0123456789abcdef", "1234").unwrap();
+        account.update_pin("4321").unwrap();
+        assert_ne!(account.code_at(59).unwrap(), "3982c0");
+        assert!(account.update_pin("12a4").is_err());
+    }
+
+    #[test]
+    fn motp_account_validation() {
+        assert!(MotpAccount::new("vpn", "", "1234").is_err());
+        assert!(MotpAccount::new("vpn", "0123456789abcdef", "").is_err());
+        assert!(MotpAccount::new("vpn", "0123456789abcdef", "pin").is_err());
+    }
+}
@@ -0,0 +1,88 @@
+use crate::models::AccountError;
+use crate::token::skey::{self, SkeyChallenge};
+
+/// An RFC 2289 (S/KEY style) account.
+/// The challenge is the one the host printed for the next login,
+/// move to the next one with [`SkeyAccount::advance`] after logging in.
+pub struct SkeyAccount {
+    name: String,
+    passphrase: String,
+    challenge: SkeyChallenge,
+}
+
+impl SkeyAccount {
+    pub fn new(name: &str, passphrase: &str, challenge: &str) -> Result<Self, AccountError> {
+        let challenge: SkeyChallenge = challenge.parse()?;
+        // Fails early on a passphrase the host would not accept
+        challenge.response(passphrase)?;
+        Ok(SkeyAccount {
+            name: name.to_string(),
+            passphrase: passphrase.to_string(),
+            challenge,
+        })
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn challenge(&self) -> &SkeyChallenge {
+        &self.challenge
+    }
+
+    /// Replaces the challenge, e.g. when the host was used from another device.
+    pub fn update_challenge(&mut self, challenge: &str) -> Result<(), AccountError> {
+        self.challenge = challenge.parse()?;
+        Ok(())
+    }
+
+    /// The response to the current challenge as six words.
+    pub fn response(&self) -> Result<String, AccountError> {
+        Ok(skey::format_words(
+            &self.challenge.response(&self.passphrase)?,
+        ))
+    }
+
+    pub fn hex_response(&self) -> Result<String, AccountError> {
+        Ok(skey::format_hex(
+            &self.challenge.response(&self.passphrase)?,
+        ))
+    }
+
+    /// Moves down the chain after the current response was used.
+    pub fn advance(&mut self) -> Result<(), AccountError> {
+        self.challenge = self
+            .challenge
+            .next()
+            .ok_or(AccountError::SequenceExhausted)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn skey_account_responses() {
+        let mut account = SkeyAccount::new("host", "This is a test.", "otp-md5 1 TeSt").unwrap();
+        assert_eq!(account.name(), "host");
+        assert_eq!(account.response().unwrap(), "EASE OIL FUM CURE AWRY AVIS");
+        assert_eq!(account.hex_response().unwrap(), "7965E05436F5029F");
+        account.advance().unwrap();
+        assert_eq!(account.challenge().to_string(), "otp-md5 0 test");
+        assert_eq!(account.response().unwrap(), "INCH SEA ANNE LONG AHEM TOUR");
+        assert!(account.advance().is_err());
+
+        account.update_challenge("otp-sha1 99 TeSt").unwrap();
+        assert_eq!(account.response().unwrap(), "GAFF WAIT SKID GIG SKY EYED");
+    }
+
+    #[test]
+    fn skey_account_validation() {
+        assert!(SkeyAccount::new("host", "This is a test.", "otp-md4 1 TeSt").is_err());
+        assert!(SkeyAccount::new("host", "short", "otp-md5 1 TeSt").is_err());
+        let mut account = SkeyAccount::new("host", "This is a test.", "otp-md5 1 TeSt").unwrap();
+        assert!(account.update_challenge("otp-md5 TeSt").is_err());
+    }
+}
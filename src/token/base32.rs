@@ -0,0 +1,178 @@
+use thiserror::Error;
+
+// RFC 4648 section 6, "The Base 32 Alphabet"
+const ALPHABET: &[u8; 32] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567";
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum Base32Error {
+    #[error("invalid base32 character {0:?} at position {1}")]
+    InvalidCharacter(char, usize),
+    #[error("invalid base32 length")]
+    InvalidLength,
+    #[error("invalid base32 padding")]
+    InvalidPadding,
+}
+
+fn decode_symbol(c: char) -> Option<u8> {
+    let upper = c.to_ascii_uppercase();
+    ALPHABET
+        .iter()
+        .position(|&x| char::from(x) == upper)
+        .map(|x| x as u8)
+}
+
+/// Decodes an RFC 4648 base32 string.
+/// Padding is optional, case is ignored, and whitespace or hyphens used as
+/// group separators (as shown by most authenticator apps) are skipped.
+pub fn decode(input: &str) -> Result<Vec<u8>, Base32Error> {
+    let mut output = Vec::with_capacity(input.len() * 5 / 8);
+    let mut buffer: u32 = 0;
+    let mut buffered_bits = 0;
+    let mut symbols = 0;
+    let mut padding = 0;
+
+    for (position, c) in input.chars().enumerate() {
+        if c.is_whitespace() || c == '-' {
+            continue;
+        }
+        if c == '=' {
+            padding += 1;
+            continue;
+        }
+        if padding > 0 {
+            // Data after the padding has started
+            return Err(Base32Error::InvalidPadding);
+        }
+        let value = decode_symbol(c).ok_or(Base32Error::InvalidCharacter(c, position))?;
+        buffer = (buffer << 5) | u32::from(value);
+        buffered_bits += 5;
+        symbols += 1;
+        if buffered_bits >= 8 {
+            buffered_bits -= 8;
+            output.push((buffer >> buffered_bits) as u8);
+        }
+    }
+
+    // A final quantum can only hold 8, 16, 24, 32 or 40 bits,
+    // i.e. 2, 4, 5, 7 or 8 symbols.
+    if matches!(symbols % 8, 1 | 3 | 6) {
+        return Err(Base32Error::InvalidLength);
+    }
+    if padding > 0 && (symbols % 8 == 0 || (symbols + padding) % 8 != 0) {
+        return Err(Base32Error::InvalidPadding);
+    }
+
+    Ok(output)
+}
+
+/// Encodes bytes as RFC 4648 base32 without padding,
+/// as expected in the secret of an otpauth URI.
+pub fn encode(data: &[u8]) -> String {
+    let mut output = String::with_capacity((data.len() * 8).div_ceil(5));
+    let mut buffer: u32 = 0;
+    let mut buffered_bits = 0;
+
+    for &byte in data {
+        buffer = (buffer << 8) | u32::from(byte);
+        buffered_bits += 8;
+        while buffered_bits >= 5 {
+            buffered_bits -= 5;
+            output.push(char::from(
+                ALPHABET[((buffer >> buffered_bits) & 0x1f) as usize],
+            ));
+        }
+    }
+    if buffered_bits > 0 {
+        // Remaining bits are padded with zeros on the right
+        output.push(char::from(
+            ALPHABET[((buffer << (5 - buffered_bits)) & 0x1f) as usize],
+        ));
+    }
+
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rfc_test_vectors() {
+        // RFC 4648 section 10, "Test Vectors"
+        let vectors = [
+            ("", ""),
+            ("f", "MY======"),
+            ("fo", "MZXQ===="),
+            ("foo", "MZXW6==="),
+            ("foob", "MZXW6YQ="),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI======"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(plain.as_bytes(), decode(encoded).unwrap());
+        }
+    }
+
+    #[test]
+    fn rfc_test_vectors_encode() {
+        let vectors = [
+            ("", ""),
+            ("f", "MY"),
+            ("fo", "MZXQ"),
+            ("foo", "MZXW6"),
+            ("foob", "MZXW6YQ"),
+            ("fooba", "MZXW6YTB"),
+            ("foobar", "MZXW6YTBOI"),
+        ];
+        for (plain, encoded) in vectors {
+            assert_eq!(encoded, encode(plain.as_bytes()));
+        }
+        assert_eq!(
+            "GEZDGNBVGY3TQOJQGEZDGNBVGY3TQOJQ",
+            encode(b"12345678901234567890")
+        );
+    }
+
+    #[test]
+    fn encode_round_trip() {
+        let data = (0..=255).collect::<Vec<u8>>();
+        for length in 0..data.len() {
+            assert_eq!(data[..length], decode(&encode(&data[..length])).unwrap());
+        }
+    }
+
+    #[test]
+    fn padding_is_optional() {
+        assert_eq!(b"foobar".to_vec(), decode("MZXW6YTBOI").unwrap());
+        assert_eq!(b"f".to_vec(), decode("MY").unwrap());
+    }
+
+    #[test]
+    fn case_and_separators_are_ignored() {
+        assert_eq!(
+            b"12345678901234567890".to_vec(),
+            decode("gezd gnbv-gy3t qojq GEZD GNBV gy3t-qojq").unwrap()
+        );
+        assert_eq!(b"foo".to_vec(), decode("mzxw6\n===").unwrap());
+    }
+
+    #[test]
+    fn invalid_character() {
+        assert_eq!(Err(Base32Error::InvalidCharacter('1', 2)), decode("MZ1W6"));
+        assert_eq!(Err(Base32Error::InvalidCharacter('8', 0)), decode("8ZXW6"));
+    }
+
+    #[test]
+    fn invalid_length() {
+        assert_eq!(Err(Base32Error::InvalidLength), decode("M"));
+        assert_eq!(Err(Base32Error::InvalidLength), decode("MZX"));
+        assert_eq!(Err(Base32Error::InvalidLength), decode("MZXW6Y"));
+    }
+
+    #[test]
+    fn invalid_padding() {
+        assert_eq!(Err(Base32Error::InvalidPadding), decode("MY="));
+        assert_eq!(Err(Base32Error::InvalidPadding), decode("MY======MY======"));
+        assert_eq!(Err(Base32Error::InvalidPadding), decode("MZXW6YTB========"));
+    }
+}
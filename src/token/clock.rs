@@ -0,0 +1,80 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Source of the current unix time, in seconds.
+pub trait Clock {
+    fn now(&self) -> i64;
+}
+
+/// The system's wall clock.
+#[derive(Copy, Clone, Debug, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> i64 {
+        match SystemTime::now().duration_since(UNIX_EPOCH) {
+            Ok(x) => x.as_secs() as i64,
+            Err(err) => -(err.duration().as_secs() as i64),
+        }
+    }
+}
+
+/// A clock stuck at a given time, mostly useful for tests.
+#[derive(Copy, Clone, Debug)]
+pub struct FixedClock {
+    time: i64,
+}
+
+impl FixedClock {
+    pub fn new(time: i64) -> Self {
+        FixedClock { time }
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> i64 {
+        self.time
+    }
+}
+
+/// Shifts another clock by a fixed number of seconds,
+/// e.g. to correct the known skew of a token.
+#[derive(Copy, Clone, Debug)]
+pub struct OffsetClock<C: Clock> {
+    inner: C,
+    offset: i64,
+}
+
+impl<C: Clock> OffsetClock<C> {
+    pub fn new(inner: C, offset: i64) -> Self {
+        OffsetClock { inner, offset }
+    }
+}
+
+impl<C: Clock> Clock for OffsetClock<C> {
+    fn now(&self) -> i64 {
+        self.inner.now().saturating_add(self.offset)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fixed_clock() {
+        assert_eq!(FixedClock::new(59).now(), 59);
+        assert_eq!(FixedClock::new(-59).now(), -59);
+    }
+
+    #[test]
+    fn offset_clock() {
+        assert_eq!(OffsetClock::new(FixedClock::new(59), 30).now(), 89);
+        assert_eq!(OffsetClock::new(FixedClock::new(59), -60).now(), -1);
+    }
+
+    #[test]
+    fn system_clock() {
+        // 2020-01-01, any sane system clock is past it
+        assert!(SystemClock.now() > 1577836800);
+    }
+}
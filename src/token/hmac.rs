@@ -1,4 +1,5 @@
 use crate::models::HmacHash;
+use crate::token::TokenError;
 use hex;
 use ring::hmac;
 
@@ -13,28 +14,62 @@ pub fn create_token() -> String {
     hex::encode(tag.as_ref())
 }
 
+fn ring_algorithm(hash_algorithm: HmacHash) -> hmac::Algorithm {
+    match hash_algorithm {
+        HmacHash::SHA1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
+        HmacHash::SHA256 => hmac::HMAC_SHA256,
+        HmacHash::SHA384 => hmac::HMAC_SHA384,
+        HmacHash::SHA512 => hmac::HMAC_SHA512,
+    }
+}
+
+/// HMAC key derived once and reused for every message it signs,
+/// e.g. successive timesteps or a window of HOTP counters.
+pub struct PreparedKey {
+    key: hmac::Key,
+    hash_algorithm: HmacHash,
+}
+
+impl PreparedKey {
+    pub fn new(key: &[u8], hash_algorithm: HmacHash) -> Result<Self, TokenError> {
+        if key.is_empty() {
+            return Err(TokenError::EmptyKey);
+        }
+        Ok(PreparedKey {
+            key: hmac::Key::new(ring_algorithm(hash_algorithm), key),
+            hash_algorithm,
+        })
+    }
+
+    pub fn hash_algorithm(&self) -> HmacHash {
+        self.hash_algorithm
+    }
+
+    pub fn sign(&self, message: &[u8]) -> hmac::Tag {
+        hmac::sign(&self.key, message)
+    }
+}
+
+/// Byte oriented version of [`compute_hmac`].
+/// The returned tag lives on the stack, no allocation is made.
+pub fn compute_hmac_bytes(
+    key: &[u8],
+    message: &[u8],
+    hash_algorithm: HmacHash,
+) -> Result<hmac::Tag, TokenError> {
+    Ok(PreparedKey::new(key, hash_algorithm)?.sign(message))
+}
+
 pub fn compute_hmac(
     hex_encoded_key: &str,
     hex_encoded_message: &str,
     hash_algorithm: HmacHash,
-) -> String {
-    let hmac_key = match hex::decode(hex_encoded_key) {
-        Ok(x) => x,
-        Err(_) => vec![0],
-    };
-    let algo = match hash_algorithm {
-        HmacHash::SHA1 => hmac::HMAC_SHA1_FOR_LEGACY_USE_ONLY,
-        HmacHash::SHA256 => hmac::HMAC_SHA256,
-        HmacHash::SHA512 => hmac::HMAC_SHA512,
-    };
-    let msg = match hex::decode(hex_encoded_message) {
-        Ok(x) => x,
-        Err(_) => vec![0],
-    };
-    let hmac_key = hmac::Key::new(algo, hmac_key.as_ref());
-    let tag = hmac::sign(&hmac_key, msg.as_ref());
+) -> Result<String, TokenError> {
+    let hmac_key = hex::decode(hex_encoded_key).map_err(TokenError::InvalidKeyEncoding)?;
+    let msg = hex::decode(hex_encoded_message).map_err(TokenError::InvalidMessageEncoding)?;
+    let tag = compute_hmac_bytes(&hmac_key, &msg, hash_algorithm)?;
 
-    hex::encode(tag.as_ref())
+    Ok(hex::encode(tag.as_ref()))
 }
 
 #[cfg(test)]
@@ -51,19 +86,25 @@ mod tests {
 
         assert_eq!(
             "b617318655057264e28bc0b6fb378c8ef146be00",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1).unwrap()
         );
         assert_eq!(
             "b0344c61d8db38535ca8afceaf0bf12b\
              881dc200c9833da726e9376c2e32cff7",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256).unwrap()
+        );
+        assert_eq!(
+            "afd03944d84895626b0825f4ab46907f\
+             15f9dadbe4101ec682aa034c7cebc59c\
+             faea9ea9076ede7f4af152e8b2fa9cb6",
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA384).unwrap()
         );
         assert_eq!(
             "87aa7cdea5ef619d4ff0b4241a1d6cb0\
              2379f4e2ce4ec2787ad0b30545e17cde\
              daa833b7d6b8a702038b274eaea3f4e4\
              be9d914eeb61f1702e696c203a126854",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512).unwrap()
         );
     }
 
@@ -78,19 +119,25 @@ mod tests {
 
         assert_eq!(
             "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1).unwrap()
         );
         assert_eq!(
             "5bdcc146bf60754e6a042426089575c7\
              5a003f089d2739839dec58b964ec3843",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256).unwrap()
+        );
+        assert_eq!(
+            "af45d2e376484031617f78d2b58a6b1b\
+             9c7ef464f5a01b47e42ec3736322445e\
+             8e2240ca5e69e2c78b3239ecfab21649",
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA384).unwrap()
         );
         assert_eq!(
             "164b7a7bfcf819e2e395fbe73b56e0a3\
              87bd64222e831fd610270cd7ea250554\
              9758bf75c05a994a6d034f65f8f0e6fd\
              caeab1a34d4a6b4b636e070a38bce737",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512).unwrap()
         );
     }
 
@@ -108,19 +155,25 @@ mod tests {
 
         assert_eq!(
             "125d7342b9ac11cd91a39af48aa17b4f63f175d3",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1).unwrap()
         );
         assert_eq!(
             "773ea91e36800e46854db8ebd09181a7\
              2959098b3ef8c122d9635514ced565fe",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256).unwrap()
+        );
+        assert_eq!(
+            "88062608d3e6ad8a0aa2ace014c8a86f\
+             0aa635d947ac9febe83ef4e55966144b\
+             2a5ab39dc13814b94e3ab6e101a34f27",
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA384).unwrap()
         );
         assert_eq!(
             "fa73b0089d56a284efb0f0756c890be9\
              b1b5dbdd8ee81a3655f83e33b2279d39\
              bf3e848279a722c806b485a47e67c807\
              b946a337bee8942674278859e13292fb",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512).unwrap()
         );
     }
 
@@ -138,19 +191,25 @@ mod tests {
 
         assert_eq!(
             "4c9007f4026250c6bc8414f9bf50c86c2d7235da",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1).unwrap()
         );
         assert_eq!(
             "82558a389a443c0ea4cc819899f2083a\
              85f0faa3e578f8077a2e3ff46729665b",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256).unwrap()
+        );
+        assert_eq!(
+            "3e8a69b7783c25851933ab6290af6ca7\
+             7a9981480850009cc5577c6e1f573b4e\
+             6801dd23c4a7d679ccf8a386c674cffb",
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA384).unwrap()
         );
         assert_eq!(
             "b0ba465637458c6990e5a8c5f61d4af7\
              e576d97ff94b872de76f8050361ee3db\
              a91ca5c11aa25eb4d679275cc5788063\
              a5f19741120c4f2de2adebeb10a298dd",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512).unwrap()
         );
     }
 
@@ -166,6 +225,7 @@ mod tests {
         assert_eq!(
             "4c1a03424b55e07fe7f27be1",
             compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1)
+                .unwrap()
                 .chars()
                 .take(24)
                 .collect::<String>() // Truncated to Digest-96 as per RFC 2202
@@ -173,6 +233,15 @@ mod tests {
         assert_eq!(
             "a3b6167473100ee06e0c796c2955552b",
             compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256)
+                .unwrap()
+                .chars()
+                .take(32)
+                .collect::<String>() // Truncated to 128 bits as per RFC 4231
+        );
+        assert_eq!(
+            "3abf34c3503b2a23a46efc619baef897",
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA384)
+                .unwrap()
                 .chars()
                 .take(32)
                 .collect::<String>() // Truncated to 128 bits as per RFC 4231
@@ -180,6 +249,7 @@ mod tests {
         assert_eq!(
             "415fad6271580a531d4179bc891d87a6",
             compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512)
+                .unwrap()
                 .chars()
                 .take(32)
                 .collect::<String>() // Truncated to 128 bits as per RFC 4231
@@ -203,7 +273,7 @@ mod tests {
 
         assert_eq!(
             "aa4ae5e15272d00e95705637ce8a3b55ed402112",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1).unwrap()
         );
 
         let hex_encoded_key = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
@@ -220,14 +290,20 @@ mod tests {
         assert_eq!(
             "60e431591ee0b67f0d8a26aacbf5b77f\
              8e0bc6213728c5140546040f0ee37f54",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256).unwrap()
+        );
+        assert_eq!(
+            "4ece084485813e9088d2c63a041bc5b4\
+             4f9ef1012a2b588f3cd11f05033ac4c6\
+             0c2ef6ab4030fe8296248df163f44952",
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA384).unwrap()
         );
         assert_eq!(
             "80b24263c7c1a3ebb71493c1dd7be8b4\
              9b46d1f41b4aeec1121b013783f8f352\
              6b56d037e05f2598bd0fd2215d6a1e52\
              95e64f73f63f0aec8b915a985d786598",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512).unwrap()
         );
     }
 
@@ -249,7 +325,7 @@ mod tests {
 
         assert_eq!(
             "e8e99d0f45237d786d6bbaa7965c7808bbff1a91",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA1).unwrap()
         );
 
         let hex_encoded_key = "aaaaaaaaaaaaaaaaaaaaaaaaaaaaaaaa\
@@ -279,14 +355,74 @@ mod tests {
         assert_eq!(
             "9b09ffa71b942fcb27635fbcd5b0e944\
              bfdc63644f0713938a7f51535c3a35e2",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA256).unwrap()
+        );
+        assert_eq!(
+            "6617178e941f020d351e2f254e8fd32c\
+             602420feb0b8fb9adccebb82461e99c5\
+             a678cc31e799176d3860e6110c46523e",
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA384).unwrap()
         );
         assert_eq!(
             "e37b6a775dc87dbaa4dfa9f96e5e3ffd\
              debd71f8867289865df5a32d20cdc944\
              b6022cac3c4982b10d5eeb55c3e4de15\
              134676fb6de0446065c97440fa8c6a58",
-            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512)
+            compute_hmac(hex_encoded_key, hex_encoded_message, HmacHash::SHA512).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_inputs() {
+        assert_eq!(
+            Err(TokenError::InvalidKeyEncoding(
+                hex::FromHexError::InvalidHexCharacter { c: 'x', index: 1 }
+            )),
+            compute_hmac("0x0b", "4869205468657265", HmacHash::SHA1)
+        );
+        assert_eq!(
+            Err(TokenError::InvalidMessageEncoding(
+                hex::FromHexError::OddLength
+            )),
+            compute_hmac("0b0b", "486", HmacHash::SHA1)
+        );
+        assert_eq!(
+            Err(TokenError::EmptyKey),
+            compute_hmac("", "4869205468657265", HmacHash::SHA1)
+        );
+    }
+
+    #[test]
+    fn byte_api_matches_hex_api() {
+        let tag =
+            compute_hmac_bytes(b"Jefe", b"what do ya want for nothing?", HmacHash::SHA256).unwrap();
+        assert_eq!(
+            "5bdcc146bf60754e6a042426089575c7\
+             5a003f089d2739839dec58b964ec3843",
+            hex::encode(tag.as_ref())
         );
+        assert_eq!(
+            Err(TokenError::EmptyKey),
+            compute_hmac_bytes(b"", b"Hi There", HmacHash::SHA1).map(|_| ())
+        );
+    }
+
+    #[test]
+    fn prepared_key_reuse() {
+        let key = PreparedKey::new(b"Jefe", HmacHash::SHA512).unwrap();
+        assert_eq!(key.hash_algorithm(), HmacHash::SHA512);
+        for _ in 0..2 {
+            assert_eq!(
+                "164b7a7bfcf819e2e395fbe73b56e0a3\
+                 87bd64222e831fd610270cd7ea250554\
+                 9758bf75c05a994a6d034f65f8f0e6fd\
+                 caeab1a34d4a6b4b636e070a38bce737",
+                hex::encode(key.sign(b"what do ya want for nothing?").as_ref())
+            );
+        }
+        assert!(matches!(
+            PreparedKey::new(b"", HmacHash::SHA1),
+            Err(TokenError::EmptyKey)
+        ));
     }
 }
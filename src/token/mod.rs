@@ -1,2 +1,37 @@
+pub mod base32;
+pub mod clock;
 pub mod hmac;
+pub mod motp;
+pub mod ocra;
 pub mod otp;
+pub mod skey;
+pub mod verify;
+pub mod yubico;
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum TokenError {
+    #[error("invalid key encoding: {0}")]
+    InvalidKeyEncoding(hex::FromHexError),
+    #[error("invalid message encoding: {0}")]
+    InvalidMessageEncoding(hex::FromHexError),
+    #[error("key is empty")]
+    EmptyKey,
+    #[error("unsupported number of digits: {0}")]
+    UnsupportedDigits(u8),
+    #[error("invalid step period: {0}")]
+    InvalidPeriod(u32),
+    #[error("resync needs at least two consecutive codes, got {0}")]
+    TooFewResyncCodes(usize),
+    #[error("invalid ocra suite: {0}")]
+    InvalidOcraSuite(String),
+    #[error("invalid ocra input: {0}")]
+    InvalidOcraInput(String),
+    #[error("invalid s/key input: {0}")]
+    InvalidSkeyInput(String),
+    #[error("invalid yubico otp: {0}")]
+    InvalidYubicoOtp(String),
+    #[error("invalid code alphabet: {0}")]
+    InvalidAlphabet(String),
+}
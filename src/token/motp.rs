@@ -0,0 +1,95 @@
+use crate::token::clock::Clock;
+use crate::token::verify::Window;
+use crate::token::TokenError;
+use md5::{Digest, Md5};
+use ring::constant_time;
+
+/// mOTP codes change every 10 seconds.
+pub const MOTP_PERIOD: i64 = 10;
+pub const MOTP_LENGTH: usize = 6;
+
+fn motp_for_timestep(secret: &str, pin: &str, timestep: i64) -> String {
+    let digest = Md5::digest(format!("{timestep}{secret}{pin}"));
+    let mut code = hex::encode(digest);
+    code.truncate(MOTP_LENGTH);
+    code
+}
+
+/// Mobile-OTP code: the first 6 hex characters of
+/// MD5(epoch / 10 || secret || PIN).
+pub fn compute_motp(secret: &str, pin: &str, time: i64) -> Result<String, TokenError> {
+    if secret.is_empty() {
+        return Err(TokenError::EmptyKey);
+    }
+    Ok(motp_for_timestep(secret, pin, time.div_euclid(MOTP_PERIOD)))
+}
+
+pub fn compute_motp_now(secret: &str, pin: &str, clock: &dyn Clock) -> Result<String, TokenError> {
+    compute_motp(secret, pin, clock.now())
+}
+
+/// Checks an mOTP code against the 10 second timesteps around `time`.
+/// Returns the timestep that matched.
+/// Most mOTP servers accept up to 3 minutes of drift, i.e. `Window::new(18, 18)`.
+pub fn verify_motp(
+    secret: &str,
+    pin: &str,
+    code: &str,
+    time: i64,
+    window: Window,
+) -> Result<Option<i64>, TokenError> {
+    if secret.is_empty() {
+        return Err(TokenError::EmptyKey);
+    }
+    let code = code.to_ascii_lowercase();
+    let expected = time.div_euclid(MOTP_PERIOD);
+    let first = expected.saturating_sub(i64::from(window.behind));
+    let last = expected.saturating_add(i64::from(window.ahead));
+    // Every candidate is checked, so the time taken
+    // does not reveal which step matched.
+    let mut matched = None;
+    for step in first..=last {
+        let candidate = motp_for_timestep(secret, pin, step);
+        if constant_time::verify_slices_are_equal(candidate.as_bytes(), code.as_bytes()).is_ok()
+            && matched.is_none()
+        {
+            matched = Some(step);
+        }
+    }
+    Ok(matched)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SECRET: &str = "0123456789abcdef";
+    const PIN: &str = "1234";
+
+    #[test]
+    fn motp_codes() {
+        let time_otp_list = [
+            (0, "41e571"),
+            (59, "3982c0"),
+            (1111111109, "063dcf"),
+            (1234567890, "f41e13"),
+        ];
+        for (time, otp) in time_otp_list {
+            assert_eq!(otp, compute_motp(SECRET, PIN, time).unwrap());
+        }
+        // Same 10 second step
+        assert_eq!("3982c0", compute_motp(SECRET, PIN, 50).unwrap());
+        assert_ne!("3982c0", compute_motp(SECRET, "4321", 59).unwrap());
+        assert_eq!(Err(TokenError::EmptyKey), compute_motp("", PIN, 59));
+    }
+
+    #[test]
+    fn motp_verification() {
+        let window = Window::new(1, 1);
+        assert_eq!(verify_motp(SECRET, PIN, "3982c0", 59, window), Ok(Some(5)));
+        assert_eq!(verify_motp(SECRET, PIN, "3982C0", 69, window), Ok(Some(5)));
+        assert_eq!(verify_motp(SECRET, PIN, "3982c0", 79, window), Ok(None));
+        assert_eq!(verify_motp(SECRET, "4321", "3982c0", 59, window), Ok(None));
+        assert_eq!(verify_motp(SECRET, PIN, "3982c", 59, window), Ok(None));
+    }
+}
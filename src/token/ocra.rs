@@ -0,0 +1,554 @@
+use crate::models::HmacHash;
+use crate::token::hmac::PreparedKey;
+use crate::token::otp::{check_digits, format_code, truncate};
+use crate::token::TokenError;
+use byteorder::{BigEndian, ByteOrder};
+use ring::{constant_time, digest};
+use std::str::FromStr;
+
+// Questions are always padded to 128 bytes, RFC 6287 section 5.1
+const QUESTION_LENGTH: usize = 128;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum QuestionFormat {
+    Alphanumeric,
+    Numeric,
+    Hex,
+}
+
+/// An OCRA suite (RFC 6287 section 6), e.g. `OCRA-1:HOTP-SHA256-8:QN08-PSHA1`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct OcraSuite {
+    suite: String,
+    hash_algorithm: HmacHash,
+    digits: u8,
+    counter: bool,
+    question_format: QuestionFormat,
+    question_length: u8,
+    password_hash: Option<HmacHash>,
+    session_length: Option<u16>,
+    timestep: Option<u32>,
+}
+
+enum Password {
+    Plain(String),
+    Hashed(Vec<u8>),
+}
+
+/// Values for the data inputs of an OCRA suite.
+/// Only the inputs listed in the suite are used.
+pub struct OcraInput {
+    questions: Vec<String>,
+    counter: Option<i64>,
+    password: Option<Password>,
+    session: Option<Vec<u8>>,
+    time: Option<i64>,
+}
+
+impl OcraInput {
+    /// The question may be as long as the suite's `QAxx`, `QNxx` or `QHxx` allows.
+    pub fn new(question: &str) -> Self {
+        Self::with_questions(vec![question.to_string()])
+    }
+
+    /// For mutual challenge-response (RFC 6287 section 7.3), the question
+    /// is both challenges one after the other, each up to the suite's length.
+    pub fn mutual(first: &str, second: &str) -> Self {
+        Self::with_questions(vec![first.to_string(), second.to_string()])
+    }
+
+    fn with_questions(questions: Vec<String>) -> Self {
+        OcraInput {
+            questions,
+            counter: None,
+            password: None,
+            session: None,
+            time: None,
+        }
+    }
+
+    pub fn with_counter(mut self, counter: i64) -> Self {
+        self.counter = Some(counter);
+        self
+    }
+
+    /// The PIN is hashed with the suite's password hash function.
+    pub fn with_password(mut self, password: &str) -> Self {
+        self.password = Some(Password::Plain(password.to_string()));
+        self
+    }
+
+    /// For servers that only keep the hash of the PIN.
+    pub fn with_password_hash(mut self, password_hash: &[u8]) -> Self {
+        self.password = Some(Password::Hashed(password_hash.to_vec()));
+        self
+    }
+
+    pub fn with_session(mut self, session: &[u8]) -> Self {
+        self.session = Some(session.to_vec());
+        self
+    }
+
+    /// Unix time, converted to time-steps of the suite's timestamp granularity.
+    pub fn with_time(mut self, time: i64) -> Self {
+        self.time = Some(time);
+        self
+    }
+}
+
+fn parse_hash(name: &str) -> Option<HmacHash> {
+    match name {
+        "SHA1" => Some(HmacHash::SHA1),
+        "SHA256" => Some(HmacHash::SHA256),
+        "SHA384" => Some(HmacHash::SHA384),
+        "SHA512" => Some(HmacHash::SHA512),
+        _ => None,
+    }
+}
+
+fn digest_algorithm(hash_algorithm: HmacHash) -> &'static digest::Algorithm {
+    match hash_algorithm {
+        HmacHash::SHA1 => &digest::SHA1_FOR_LEGACY_USE_ONLY,
+        HmacHash::SHA256 => &digest::SHA256,
+        HmacHash::SHA384 => &digest::SHA384,
+        HmacHash::SHA512 => &digest::SHA512,
+    }
+}
+
+/// Parses the `G` part of a `TG` timestamp input, e.g. `30S`, `1M` or `24H`.
+fn parse_timestep(granularity: &str) -> Option<u32> {
+    let (index, unit) = granularity.char_indices().last()?;
+    let value: u32 = granularity[..index].parse().ok()?;
+    match unit {
+        'S' if (1..=59).contains(&value) => Some(value),
+        'M' if (1..=59).contains(&value) => Some(value * 60),
+        'H' if (1..=48).contains(&value) => Some(value * 3600),
+        _ => None,
+    }
+}
+
+/// Hex digits of a decimal number of arbitrary length, without leading zeros.
+fn decimal_to_hex(decimal: &str) -> Option<String> {
+    // Little endian base 256 digits
+    let mut value: Vec<u8> = vec![0];
+    for c in decimal.chars() {
+        let mut carry = c.to_digit(10)?;
+        for byte in value.iter_mut() {
+            let x = u32::from(*byte) * 10 + carry;
+            *byte = (x & 0xff) as u8;
+            carry = x >> 8;
+        }
+        if carry > 0 {
+            value.push(carry as u8);
+        }
+    }
+    let hex_value: String = value.iter().rev().map(|x| format!("{x:02X}")).collect();
+    let trimmed = hex_value.trim_start_matches('0');
+    if trimmed.is_empty() {
+        Some("0".to_string())
+    } else {
+        Some(trimmed.to_string())
+    }
+}
+
+impl FromStr for OcraSuite {
+    type Err = TokenError;
+    fn from_str(s: &str) -> Result<Self, TokenError> {
+        let invalid = || TokenError::InvalidOcraSuite(s.to_string());
+        let parts = s.split(':').collect::<Vec<_>>();
+        let [version, function, data_input] = parts[..] else {
+            return Err(invalid());
+        };
+        if version != "OCRA-1" {
+            return Err(invalid());
+        }
+
+        let (hash, digits) = function
+            .strip_prefix("HOTP-")
+            .and_then(|x| x.split_once('-'))
+            .ok_or_else(invalid)?;
+        let hash_algorithm = parse_hash(hash).ok_or_else(invalid)?;
+        let digits: u8 = digits.parse().map_err(|_| invalid())?;
+        // Untruncated responses ("-0") are not supported
+        if digits < 4 {
+            return Err(TokenError::UnsupportedDigits(digits));
+        }
+        check_digits(digits)?;
+
+        let mut counter = false;
+        let mut question = None;
+        let mut password_hash = None;
+        let mut session_length = None;
+        let mut timestep = None;
+        for input in data_input.split('-') {
+            match input.split_at_checked(1).ok_or_else(invalid)? {
+                ("C", "") if !counter => counter = true,
+                ("Q", format) if question.is_none() => {
+                    let (format, length) = format.split_at_checked(1).ok_or_else(invalid)?;
+                    let format = match format {
+                        "A" => QuestionFormat::Alphanumeric,
+                        "N" => QuestionFormat::Numeric,
+                        "H" => QuestionFormat::Hex,
+                        _ => return Err(invalid()),
+                    };
+                    let length: u8 = match length.parse() {
+                        Ok(x) if length.len() == 2 && (4..=64).contains(&x) => x,
+                        _ => return Err(invalid()),
+                    };
+                    question = Some((format, length));
+                }
+                ("P", hash) if password_hash.is_none() => {
+                    password_hash = Some(parse_hash(hash).ok_or_else(invalid)?);
+                }
+                ("S", length) if session_length.is_none() => {
+                    session_length = match length.parse() {
+                        Ok(x) if length.len() == 3 => Some(x),
+                        _ => return Err(invalid()),
+                    };
+                }
+                ("T", granularity) if timestep.is_none() => {
+                    timestep = Some(parse_timestep(granularity).ok_or_else(invalid)?);
+                }
+                _ => return Err(invalid()),
+            }
+        }
+        // The question is the only mandatory data input
+        let (question_format, question_length) = question.ok_or_else(invalid)?;
+
+        Ok(OcraSuite {
+            suite: s.to_string(),
+            hash_algorithm,
+            digits,
+            counter,
+            question_format,
+            question_length,
+            password_hash,
+            session_length,
+            timestep,
+        })
+    }
+}
+
+impl OcraSuite {
+    pub fn hash_algorithm(&self) -> HmacHash {
+        self.hash_algorithm
+    }
+
+    pub fn digits(&self) -> u8 {
+        self.digits
+    }
+
+    pub fn question_format(&self) -> QuestionFormat {
+        self.question_format
+    }
+
+    pub fn question_length(&self) -> u8 {
+        self.question_length
+    }
+
+    fn encode_question(&self, questions: &[String]) -> Result<Vec<u8>, TokenError> {
+        let question = questions.concat();
+        let invalid = || TokenError::InvalidOcraInput(format!("question {question:?}"));
+        // QN08 means up to 8 digits, RFC 6287 section 5.1
+        let max_length = usize::from(self.question_length);
+        if questions.iter().any(|x| x.chars().count() > max_length) {
+            return Err(invalid());
+        }
+        let mut hex_question = match self.question_format {
+            QuestionFormat::Alphanumeric => hex::encode(&question),
+            QuestionFormat::Numeric => decimal_to_hex(&question).ok_or_else(invalid)?,
+            QuestionFormat::Hex => question.clone(),
+        };
+        if hex_question.len() > QUESTION_LENGTH * 2 {
+            return Err(invalid());
+        }
+        // Left aligned, padded with zeros on the right
+        hex_question.extend(std::iter::repeat_n(
+            '0',
+            QUESTION_LENGTH * 2 - hex_question.len(),
+        ));
+        hex::decode(hex_question).map_err(|_| invalid())
+    }
+
+    fn message(&self, input: &OcraInput) -> Result<Vec<u8>, TokenError> {
+        let missing = |name: &str| TokenError::InvalidOcraInput(format!("missing {name}"));
+        let mut message = Vec::with_capacity(self.suite.len() + 1 + 8 + QUESTION_LENGTH);
+        message.extend_from_slice(self.suite.as_bytes());
+        message.push(0);
+
+        if self.counter {
+            let counter = input.counter.ok_or_else(|| missing("counter"))?;
+            message.extend_from_slice(&counter.to_be_bytes());
+        }
+
+        message.extend(self.encode_question(&input.questions)?);
+
+        if let Some(hash_algorithm) = self.password_hash {
+            let password_hash = match &input.password {
+                Some(Password::Plain(x)) => {
+                    digest::digest(digest_algorithm(hash_algorithm), x.as_bytes())
+                        .as_ref()
+                        .to_vec()
+                }
+                Some(Password::Hashed(x)) => x.clone(),
+                None => return Err(missing("password")),
+            };
+            if password_hash.len() != digest_algorithm(hash_algorithm).output_len() {
+                return Err(TokenError::InvalidOcraInput(
+                    "password hash length".to_string(),
+                ));
+            }
+            message.extend(password_hash);
+        }
+
+        if let Some(session_length) = self.session_length {
+            let session = input.session.as_ref().ok_or_else(|| missing("session"))?;
+            let padding = usize::from(session_length)
+                .checked_sub(session.len())
+                .ok_or_else(|| TokenError::InvalidOcraInput("session too long".to_string()))?;
+            // Right aligned, padded with zeros on the left
+            message.extend(std::iter::repeat_n(0, padding));
+            message.extend_from_slice(session);
+        }
+
+        if let Some(timestep) = self.timestep {
+            let time = input.time.ok_or_else(|| missing("timestamp"))?;
+            let mut timestamp = [0; 8];
+            BigEndian::write_i64(&mut timestamp, time.div_euclid(i64::from(timestep)));
+            message.extend_from_slice(&timestamp);
+        }
+
+        Ok(message)
+    }
+
+    /// Computes the OCRA response for `input` (RFC 6287 section 5.2).
+    pub fn compute(&self, key: &[u8], input: &OcraInput) -> Result<String, TokenError> {
+        let key = PreparedKey::new(key, self.hash_algorithm)?;
+        let mac = key.sign(&self.message(input)?);
+
+        Ok(format_code(
+            truncate(mac.as_ref(), self.digits),
+            self.digits,
+        ))
+    }
+
+    /// Checks a response in constant time.
+    pub fn verify(
+        &self,
+        key: &[u8],
+        input: &OcraInput,
+        response: &str,
+    ) -> Result<bool, TokenError> {
+        let expected = self.compute(key, input)?;
+
+        Ok(
+            constant_time::verify_slices_are_equal(expected.as_bytes(), response.as_bytes())
+                .is_ok(),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    // Test vectors from RFC 6287 appendix C
+    use super::*;
+
+    const SEED: &[u8] = b"12345678901234567890";
+    const SEED32: &[u8] = b"12345678901234567890\
+                            123456789012";
+    const SEED64: &[u8] = b"12345678901234567890\
+                            12345678901234567890\
+                            12345678901234567890\
+                            1234";
+    const PIN: &str = "1234";
+
+    #[test]
+    fn parse_suite() {
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA256-8:C-QN08-PSHA1").unwrap();
+        assert_eq!(suite.hash_algorithm(), HmacHash::SHA256);
+        assert_eq!(suite.digits(), 8);
+        assert!(suite.counter);
+        assert_eq!(suite.question_format(), QuestionFormat::Numeric);
+        assert_eq!(suite.question_length(), 8);
+        assert_eq!(suite.password_hash, Some(HmacHash::SHA1));
+        assert_eq!(suite.session_length, None);
+        assert_eq!(suite.timestep, None);
+
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA512-8:QA10-S064-T1M").unwrap();
+        assert!(!suite.counter);
+        assert_eq!(suite.question_format(), QuestionFormat::Alphanumeric);
+        assert_eq!(suite.session_length, Some(64));
+        assert_eq!(suite.timestep, Some(60));
+
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA1-6:QH40-T30S").unwrap();
+        assert_eq!(suite.timestep, Some(30));
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA1-6:QH40-T24H").unwrap();
+        assert_eq!(suite.timestep, Some(24 * 3600));
+    }
+
+    #[test]
+    fn parse_invalid_suite() {
+        let suites = [
+            "OCRA-2:HOTP-SHA1-6:QN08",
+            "OCRA-1:HOTP-MD5-6:QN08",
+            "OCRA-1:TOTP-SHA1-6:QN08",
+            "OCRA-1:HOTP-SHA1-6",
+            "OCRA-1:HOTP-SHA1-6:C",
+            "OCRA-1:HOTP-SHA1-6:QN8",
+            "OCRA-1:HOTP-SHA1-6:QN65",
+            "OCRA-1:HOTP-SHA1-6:QX08",
+            "OCRA-1:HOTP-SHA1-6:QN08-QN08",
+            "OCRA-1:HOTP-SHA1-6:QN08-PMD5",
+            "OCRA-1:HOTP-SHA1-6:QN08-S64",
+            "OCRA-1:HOTP-SHA1-6:QN08-T60M",
+            "OCRA-1:HOTP-SHA1-6:QN08-X",
+            "OCRA-1:HOTP-SHA1-6:QN08-Tμ",
+            "OCRA-1:HOTP-SHA1-6:QN08-T1μ",
+        ];
+        for suite in suites {
+            assert_eq!(
+                OcraSuite::from_str(suite),
+                Err(TokenError::InvalidOcraSuite(suite.to_string()))
+            );
+        }
+        assert_eq!(
+            OcraSuite::from_str("OCRA-1:HOTP-SHA1-3:QN08"),
+            Err(TokenError::UnsupportedDigits(3))
+        );
+    }
+
+    #[test]
+    fn one_way_challenge_response() {
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA1-6:QN08").unwrap();
+        let expected = [
+            "237653", "243178", "653583", "740991", "608993", "388898", "816933", "224598",
+            "750600", "294470",
+        ];
+        for (i, response) in expected.into_iter().enumerate() {
+            let question = i.to_string().repeat(8);
+            let input = OcraInput::new(&question);
+            assert_eq!(suite.compute(SEED, &input).unwrap(), response);
+        }
+
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA256-8:C-QN08-PSHA1").unwrap();
+        let expected = [
+            "65347737", "86775851", "78192410", "71565254", "10104329", "65983500", "70069104",
+            "91771096", "75011558", "08522129",
+        ];
+        for (counter, response) in expected.into_iter().enumerate() {
+            let input = OcraInput::new("12345678")
+                .with_counter(counter as i64)
+                .with_password(PIN);
+            assert_eq!(suite.compute(SEED32, &input).unwrap(), response);
+        }
+
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA256-8:QN08-PSHA1").unwrap();
+        let expected = ["83238735", "01501458", "17957585", "86776967", "86807031"];
+        let pin_hash = hex::decode("7110eda4d09e062aa5e4a390b0a572ac0d2c0220").unwrap();
+        for (i, response) in expected.into_iter().enumerate() {
+            let question = i.to_string().repeat(8);
+            let input = OcraInput::new(&question).with_password_hash(&pin_hash);
+            assert_eq!(suite.compute(SEED32, &input).unwrap(), response);
+        }
+
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA512-8:C-QN08").unwrap();
+        let expected = [
+            "07016083", "63947962", "70123924", "25341727", "33203315", "34205738", "44343969",
+            "51946085", "20403879", "31409299",
+        ];
+        for (counter, response) in expected.into_iter().enumerate() {
+            let question = counter.to_string().repeat(8);
+            let input = OcraInput::new(&question).with_counter(counter as i64);
+            assert_eq!(suite.compute(SEED64, &input).unwrap(), response);
+        }
+
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA512-8:QN08-T1M").unwrap();
+        let expected = ["95209754", "55907591", "22048402", "24218844", "36209546"];
+        for (i, response) in expected.into_iter().enumerate() {
+            let question = i.to_string().repeat(8);
+            // Time-step "132d0b6" in minutes
+            let input = OcraInput::new(&question).with_time(0x132d0b6 * 60 + 59);
+            assert_eq!(suite.compute(SEED64, &input).unwrap(), response);
+        }
+    }
+
+    #[test]
+    fn mutual_challenge_response() {
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA256-8:QA08").unwrap();
+        let server_input = OcraInput::mutual("CLI22220", "SRV11110");
+        let client_input = OcraInput::mutual("SRV11110", "CLI22220");
+        assert_eq!(suite.compute(SEED32, &server_input).unwrap(), "28247970");
+        assert_eq!(suite.compute(SEED32, &client_input).unwrap(), "15510767");
+
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA512-8:QA08-PSHA1").unwrap();
+        let client_input = OcraInput::mutual("SRV11110", "CLI22220").with_password(PIN);
+        assert_eq!(suite.compute(SEED64, &client_input).unwrap(), "18806276");
+    }
+
+    #[test]
+    fn plain_signature() {
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA256-8:QA08").unwrap();
+        let input = OcraInput::new("SIG10000");
+        assert_eq!(suite.compute(SEED32, &input).unwrap(), "53095496");
+        assert!(suite.verify(SEED32, &input, "53095496").unwrap());
+        assert!(!suite.verify(SEED32, &input, "53095497").unwrap());
+        assert!(!suite.verify(SEED32, &input, "5309549").unwrap());
+
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA512-8:QA10-T1M").unwrap();
+        let input = OcraInput::new("SIG1000000").with_time(0x132d0b6 * 60);
+        assert_eq!(suite.compute(SEED64, &input).unwrap(), "77537423");
+    }
+
+    #[test]
+    fn session_information() {
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA1-6:QN08-S004").unwrap();
+        let short = OcraInput::new("12345678").with_session(b"\x01\x02");
+        let padded = OcraInput::new("12345678").with_session(b"\x00\x00\x01\x02");
+        assert_eq!(
+            suite.compute(SEED, &short).unwrap(),
+            suite.compute(SEED, &padded).unwrap()
+        );
+        let too_long = OcraInput::new("12345678").with_session(b"\x01\x02\x03\x04\x05");
+        assert!(matches!(
+            suite.compute(SEED, &too_long),
+            Err(TokenError::InvalidOcraInput(_))
+        ));
+    }
+
+    #[test]
+    fn invalid_inputs() {
+        let suite = OcraSuite::from_str("OCRA-1:HOTP-SHA256-8:C-QN08-PSHA1").unwrap();
+        let missing_counter = OcraInput::new("12345678").with_password(PIN);
+        let missing_password = OcraInput::new("12345678").with_counter(0);
+        let not_numeric = OcraInput::new("1234567A")
+            .with_counter(0)
+            .with_password(PIN);
+        // QN08 allows at most 8 digits
+        let too_long = OcraInput::new("123456789")
+            .with_counter(0)
+            .with_password(PIN);
+        let mutual_too_long = OcraInput::mutual("12345678", "123456789")
+            .with_counter(0)
+            .with_password(PIN);
+        for input in [
+            missing_counter,
+            missing_password,
+            not_numeric,
+            too_long,
+            mutual_too_long,
+        ] {
+            assert!(matches!(
+                suite.compute(SEED32, &input),
+                Err(TokenError::InvalidOcraInput(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn numeric_questions() {
+        assert_eq!(decimal_to_hex("00000000"), Some("0".to_string()));
+        assert_eq!(decimal_to_hex("12345678"), Some("BC614E".to_string()));
+        assert_eq!(decimal_to_hex("4294967296"), Some("100000000".to_string()));
+        assert_eq!(decimal_to_hex("12a"), None);
+    }
+}
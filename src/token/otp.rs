@@ -1,9 +1,332 @@
 use crate::models::HmacHash;
-use crate::token::hmac;
+use crate::token::clock::Clock;
+use crate::token::hmac::PreparedKey;
+use crate::token::TokenError;
 use byteorder::{BigEndian, ByteOrder};
 
-pub fn compute_otp_lifetime(time: i64, step_period: u8) -> u8 {
-    step_period - (time % i64::from(step_period)) as u8
+/// RFC 4226 section 5.3, the 31 bit truncated value has at most 10 digits.
+pub const MAX_DIGITS: u8 = 10;
+
+/// Characters Steam Guard renders its codes with.
+pub const STEAM_ALPHABET: &[u8; 26] = b"23456789BCDFGHJKMNPQRTVWXY";
+pub const STEAM_DIGITS: u8 = 5;
+pub const STEAM_PERIOD: u32 = 30;
+
+fn check_period(step_period: u32) -> Result<(), TokenError> {
+    if step_period == 0 {
+        return Err(TokenError::InvalidPeriod(step_period));
+    }
+    Ok(())
+}
+
+/// Seconds left before the TOTP code for `time` expires.
+/// `time` counts from T0, see [`OtpGenerator::with_start_time`].
+pub fn compute_otp_lifetime(time: i64, step_period: u32) -> Result<u32, TokenError> {
+    check_period(step_period)?;
+    // rem_euclid keeps the remainder positive for pre-epoch times
+    Ok(step_period - time.rem_euclid(i64::from(step_period)) as u32)
+}
+
+pub(crate) fn check_digits(digits: u8) -> Result<(), TokenError> {
+    if digits == 0 || digits > MAX_DIGITS {
+        return Err(TokenError::UnsupportedDigits(digits));
+    }
+    Ok(())
+}
+
+/// Zero pads a code to the requested number of digits.
+pub fn format_code(code: u32, digits: u8) -> String {
+    format!("{:0>width$}", code, width = usize::from(digits))
+}
+
+/// Renders a truncated HMAC value the way Steam Guard does:
+/// five characters of [`STEAM_ALPHABET`], least significant first.
+pub fn format_steam_code(truncated_value: u32) -> String {
+    OtpEncoding::steam().encode(truncated_value, STEAM_DIGITS)
+}
+
+/// Characters a custom [`OtpEncoding`] renders codes with.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodeAlphabet(String);
+
+impl CodeAlphabet {
+    /// At least two distinct, printable ASCII characters.
+    pub fn new(alphabet: &str) -> Result<Self, TokenError> {
+        let bytes = alphabet.as_bytes();
+        let unique = bytes
+            .iter()
+            .enumerate()
+            .all(|(i, c)| !bytes[..i].contains(c));
+        if bytes.len() < 2 || !unique || !bytes.iter().all(u8::is_ascii_graphic) {
+            return Err(TokenError::InvalidAlphabet(alphabet.to_string()));
+        }
+        Ok(CodeAlphabet(alphabet.to_string()))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+/// How the truncated HMAC value is turned into a code.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum OtpEncoding {
+    /// The value modulo 10^digits, zero padded (RFC 4226 section 5.3).
+    #[default]
+    Decimal,
+    /// Lowercase hex digits, most significant first.
+    Hex,
+    /// RFC 4648 base32 characters, most significant first.
+    Base32,
+    /// Characters of a custom alphabet, least significant first like Steam Guard.
+    Alphabet(CodeAlphabet),
+}
+
+impl OtpEncoding {
+    pub fn steam() -> Self {
+        // STEAM_ALPHABET is valid ASCII with no repeated characters
+        OtpEncoding::Alphabet(CodeAlphabet(
+            String::from_utf8_lossy(STEAM_ALPHABET).into_owned(),
+        ))
+    }
+
+    /// Renders `digits` characters of `truncated_value`.
+    pub fn encode(&self, truncated_value: u32, digits: u8) -> String {
+        let (alphabet, most_significant_first) = match self {
+            OtpEncoding::Decimal => (&b"0123456789"[..], true),
+            OtpEncoding::Hex => (&b"0123456789abcdef"[..], true),
+            OtpEncoding::Base32 => (&b"ABCDEFGHIJKLMNOPQRSTUVWXYZ234567"[..], true),
+            OtpEncoding::Alphabet(alphabet) => (alphabet.as_str().as_bytes(), false),
+        };
+        let base = alphabet.len() as u32;
+        let mut value = truncated_value;
+        let mut code = (0..digits)
+            .map(|_| {
+                let c = char::from(alphabet[(value % base) as usize]);
+                value /= base;
+                c
+            })
+            .collect::<Vec<_>>();
+        if most_significant_first {
+            code.reverse();
+        }
+        code.into_iter().collect()
+    }
+}
+
+/// Reduces a truncated value to `digits` decimal digits.
+/// 10^10 doesn't fit in a u32, so the modulus is taken in 64 bits.
+fn decimal_code(truncated_value: u32, digits: u8) -> u32 {
+    // Never larger than the truncated value, so it fits back in a u32
+    (u64::from(truncated_value) % u64::pow(10, digits.into())) as u32
+}
+
+fn dynamic_truncation(mac: &[u8]) -> u32 {
+    let offset: usize = match mac.last() {
+        Some(x) => (*x & 0xf).into(),
+        None => 0,
+    };
+    BigEndian::read_u32(&mac[offset..offset + 4]) & 0x7fff_ffff
+}
+
+/// A code and the unix times it is valid for, from `valid_from`
+/// up to but not including `valid_until`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TimedCode {
+    pub code: String,
+    pub valid_from: i64,
+    pub valid_until: i64,
+}
+
+/// The TOTP codes around a point in time, for showing the upcoming
+/// code as the current one is about to roll over.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CodeWindow {
+    pub previous: TimedCode,
+    pub current: TimedCode,
+    pub next: TimedCode,
+}
+
+/// Code generator for a single account.
+/// The HMAC key is prepared once, so generating many codes
+/// (successive timesteps, look-ahead windows, resync scans) stays cheap.
+pub struct OtpGenerator {
+    key: PreparedKey,
+    digits: u8,
+    step_period: u32,
+    start_time: i64,
+    encoding: OtpEncoding,
+}
+
+impl OtpGenerator {
+    pub fn new(
+        key: &[u8],
+        hash_algorithm: HmacHash,
+        digits: u8,
+        step_period: u32,
+    ) -> Result<Self, TokenError> {
+        check_digits(digits)?;
+        check_period(step_period)?;
+        Ok(OtpGenerator {
+            key: PreparedKey::new(key, hash_algorithm)?,
+            digits,
+            step_period,
+            start_time: 0,
+            encoding: OtpEncoding::Decimal,
+        })
+    }
+
+    /// Counts TOTP timesteps from `start_time` (T0 in RFC 6238)
+    /// instead of the unix epoch.
+    pub fn with_start_time(mut self, start_time: i64) -> Self {
+        self.start_time = start_time;
+        self
+    }
+
+    /// Renders codes with `encoding` instead of decimal digits.
+    pub fn with_encoding(mut self, encoding: OtpEncoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    pub fn hash_algorithm(&self) -> HmacHash {
+        self.key.hash_algorithm()
+    }
+
+    pub fn digits(&self) -> u8 {
+        self.digits
+    }
+
+    pub fn step_period(&self) -> u32 {
+        self.step_period
+    }
+
+    pub fn start_time(&self) -> i64 {
+        self.start_time
+    }
+
+    pub fn encoding(&self) -> &OtpEncoding {
+        &self.encoding
+    }
+
+    pub fn timestep(&self, time: i64) -> i64 {
+        time.saturating_sub(self.start_time)
+            .div_euclid(i64::from(self.step_period))
+    }
+
+    /// Seconds left before the TOTP code for `time` expires.
+    pub fn remaining_seconds(&self, time: i64) -> u32 {
+        // `new` checked the period, so this can't fail
+        compute_otp_lifetime(time.saturating_sub(self.start_time), self.step_period)
+            .unwrap_or(self.step_period)
+    }
+
+    fn truncated_value(&self, counter: i64) -> u32 {
+        let mut unpacked_counter = [0; 8];
+        BigEndian::write_i64(&mut unpacked_counter, counter);
+        truncate_with_key(&self.key, unpacked_counter)
+    }
+
+    /// The decimal code for `counter`, whatever the encoding.
+    pub fn hotp_value(&self, counter: i64) -> u32 {
+        decimal_code(self.truncated_value(counter), self.digits)
+    }
+
+    pub fn totp_value(&self, time: i64) -> u32 {
+        self.hotp_value(self.timestep(time))
+    }
+
+    pub fn hotp(&self, counter: i64) -> String {
+        self.encoding
+            .encode(self.truncated_value(counter), self.digits)
+    }
+
+    pub fn totp(&self, time: i64) -> String {
+        self.hotp(self.timestep(time))
+    }
+
+    pub fn totp_now(&self, clock: &dyn Clock) -> String {
+        self.totp(clock.now())
+    }
+
+    /// Previous, current and next TOTP codes for `time`. The current code
+    /// is valid for [`OtpGenerator::remaining_seconds`] more seconds.
+    pub fn totp_window(&self, time: i64) -> CodeWindow {
+        let period = i64::from(self.step_period);
+        let timestep = self.timestep(time);
+        let current_until = time.saturating_add(i64::from(self.remaining_seconds(time)));
+        let timed_code = |offset: i64| {
+            let valid_until = current_until.saturating_add(offset * period);
+            TimedCode {
+                code: self.hotp(timestep.saturating_add(offset)),
+                valid_from: valid_until.saturating_sub(period),
+                valid_until,
+            }
+        };
+        CodeWindow {
+            previous: timed_code(-1),
+            current: timed_code(0),
+            next: timed_code(1),
+        }
+    }
+
+    pub fn totp_window_now(&self, clock: &dyn Clock) -> CodeWindow {
+        self.totp_window(clock.now())
+    }
+}
+
+/// Byte oriented version of [`compute_totp`], returning the numeric code.
+pub fn compute_totp_bytes(
+    key: &[u8],
+    time: i64,
+    digits: u8,
+    hash_algorithm: HmacHash,
+    step_period: u32,
+) -> Result<u32, TokenError> {
+    check_period(step_period)?;
+    let timestep = time.div_euclid(i64::from(step_period));
+    let mut counter = [0; 8];
+    BigEndian::write_i64(&mut counter, timestep);
+
+    compute_hotp_bytes(key, counter, digits, hash_algorithm)
+}
+
+/// Byte oriented version of [`compute_hotp`], returning the numeric code.
+/// Use [`format_code`] to get the zero padded string.
+pub fn compute_hotp_bytes(
+    key: &[u8],
+    counter: [u8; 8],
+    digits: u8,
+    hash_algorithm: HmacHash,
+) -> Result<u32, TokenError> {
+    check_digits(digits)?;
+    let key = PreparedKey::new(key, hash_algorithm)?;
+
+    Ok(hotp_with_key(&key, counter, digits))
+}
+
+fn truncate_with_key(key: &PreparedKey, counter: [u8; 8]) -> u32 {
+    let mac = key.sign(&counter);
+
+    dynamic_truncation(mac.as_ref())
+}
+
+fn hotp_with_key(key: &PreparedKey, counter: [u8; 8], digits: u8) -> u32 {
+    decimal_code(truncate_with_key(key, counter), digits)
+}
+
+/// Dynamic truncation of an HMAC value down to `digits` decimal digits.
+pub(crate) fn truncate(mac: &[u8], digits: u8) -> u32 {
+    decimal_code(dynamic_truncation(mac), digits)
+}
+
+/// Steam Guard code: SHA1 TOTP with a 30 second period,
+/// rendered with [`OtpEncoding::steam`].
+pub fn compute_steam(key: &[u8], time: i64) -> Result<String, TokenError> {
+    let generator = OtpGenerator::new(key, HmacHash::SHA1, STEAM_DIGITS, STEAM_PERIOD)?
+        .with_encoding(OtpEncoding::steam());
+
+    Ok(generator.totp(time))
 }
 
 pub fn compute_totp(
@@ -11,41 +334,48 @@ pub fn compute_totp(
     time: i64,
     digits: u8,
     hash_algorithm: HmacHash,
-    step_period: u8,
-) -> String {
-    let timestep = time / i64::from(step_period);
+    step_period: u32,
+) -> Result<String, TokenError> {
+    let key = hex::decode(key).map_err(TokenError::InvalidKeyEncoding)?;
+    let totp = compute_totp_bytes(&key, time, digits, hash_algorithm, step_period)?;
 
-    compute_hotp(key, timestep, digits, hash_algorithm)
+    Ok(format_code(totp, digits))
 }
 
-pub fn compute_hotp(key: &str, counter: i64, digits: u8, hash_algorithm: HmacHash) -> String {
+/// Previous, current and next codes for `time`, with the times they are valid for.
+/// The current code expires after [`compute_otp_lifetime`] seconds.
+pub fn compute_totp_window(
+    key: &str,
+    time: i64,
+    digits: u8,
+    hash_algorithm: HmacHash,
+    step_period: u32,
+) -> Result<CodeWindow, TokenError> {
+    let key = hex::decode(key).map_err(TokenError::InvalidKeyEncoding)?;
+    let generator = OtpGenerator::new(&key, hash_algorithm, digits, step_period)?;
+
+    Ok(generator.totp_window(time))
+}
+
+pub fn compute_hotp(
+    key: &str,
+    counter: i64,
+    digits: u8,
+    hash_algorithm: HmacHash,
+) -> Result<String, TokenError> {
     // See HTOP errata https://www.rfc-editor.org/errata/eid6702
     // Counter value is considered big endian, key is little endian
     let mut unpacked_counter = [0; 8];
     BigEndian::write_i64(&mut unpacked_counter, counter);
 
-    let hex_encoded_counter: String = hex::encode(unpacked_counter);
-    let hex_encoded_mac: String = hmac::compute_hmac(key, &hex_encoded_counter, hash_algorithm);
-    let mac: Vec<u8> = match hex::decode(hex_encoded_mac) {
-        Ok(x) => x,
-        Err(_) => vec![0],
-    };
-    let offset: usize = match mac.last() {
-        Some(x) => (*x & 0xf).into(),
-        None => 0,
-    };
-    let truncated_decimal_otp = {
-        (u32::from(mac[offset] & 0x7f) << 24)
-            | (u32::from(mac[offset + 1]) << 16)
-            | (u32::from(mac[offset + 2]) << 8)
-            | u32::from(mac[offset + 3])
-    };
-    let hotp = truncated_decimal_otp % u32::pow(10, digits.into());
+    let key = hex::decode(key).map_err(TokenError::InvalidKeyEncoding)?;
+    let hotp = compute_hotp_bytes(&key, unpacked_counter, digits, hash_algorithm)?;
 
-    format!("{:0>width$}", hotp.to_string(), width = usize::from(digits))
+    Ok(format_code(hotp, digits))
 }
 
 #[cfg(test)]
+#[allow(clippy::useless_conversion)]
 mod tests {
     use super::*;
 
@@ -72,7 +402,7 @@ mod tests {
             let counter_num: i64 = counter.try_into().unwrap();
             assert_eq!(
                 otp,
-                compute_hotp(&hex_key, counter_num, code_digits, hash_algorithm)
+                compute_hotp(&hex_key, counter_num, code_digits, hash_algorithm).unwrap()
             );
         }
     }
@@ -93,7 +423,7 @@ mod tests {
         {
             assert_eq!(
                 otp,
-                compute_totp(&hex_key, time, code_digits, hash_algorithm, step_period)
+                compute_totp(&hex_key, time, code_digits, hash_algorithm, step_period).unwrap()
             );
         }
     }
@@ -115,7 +445,7 @@ mod tests {
         {
             assert_eq!(
                 otp,
-                compute_totp(&hex_key, time, code_digits, hash_algorithm, step_period)
+                compute_totp(&hex_key, time, code_digits, hash_algorithm, step_period).unwrap()
             );
         }
     }
@@ -139,8 +469,284 @@ mod tests {
         {
             assert_eq!(
                 otp,
-                compute_totp(&hex_key, time, code_digits, hash_algorithm, step_period)
+                compute_totp(&hex_key, time, code_digits, hash_algorithm, step_period).unwrap()
             );
         }
     }
+
+    #[test]
+    fn steam_codes() {
+        let key = b"12345678901234567890";
+        let expected_otp_list = ["PV9M4", "PY4YB", "5PP3V", "VHHQY", "9N776", "R5DMB"];
+        for (time, otp) in TOTP_TIME_LIST.into_iter().zip(expected_otp_list) {
+            assert_eq!(otp, compute_steam(key, time).unwrap());
+        }
+        assert_eq!("22222", format_steam_code(0));
+        assert_eq!("32222", format_steam_code(1));
+        assert_eq!("23222", format_steam_code(26));
+    }
+
+    #[test]
+    fn encodings() {
+        let generator = OtpGenerator::new(b"12345678901234567890", HmacHash::SHA1, 6, 30).unwrap();
+        assert_eq!(generator.encoding(), &OtpEncoding::Decimal);
+        assert_eq!(generator.hotp(0), "755224");
+
+        // Truncated value for counter 0 is 0x4c93cf18 (RFC 4226 appendix D)
+        let value = 0x4c93cf18;
+        assert_eq!(OtpEncoding::Decimal.encode(value, 6), "755224");
+        assert_eq!(OtpEncoding::Hex.encode(value, 6), "93cf18");
+        assert_eq!(OtpEncoding::Hex.encode(value, 9), "04c93cf18");
+        assert_eq!(OtpEncoding::Base32.encode(value, 7), "BGJHTYY");
+        assert_eq!(OtpEncoding::Base32.encode(value, 5), "JHTYY");
+        let generator = generator.with_encoding(OtpEncoding::Hex);
+        assert_eq!(generator.hotp(0), "93cf18");
+        // The decimal value is still available
+        assert_eq!(generator.hotp_value(0), 755224);
+
+        let binary = OtpEncoding::Alphabet(CodeAlphabet::new("01").unwrap());
+        assert_eq!(binary.encode(0b1101, 6), "101100");
+    }
+
+    #[test]
+    fn invalid_alphabets() {
+        assert!(CodeAlphabet::new("23456789BCDFGHJKMNPQRTVWXY").is_ok());
+        assert_eq!(
+            CodeAlphabet::new("A"),
+            Err(TokenError::InvalidAlphabet("A".to_string()))
+        );
+        assert!(CodeAlphabet::new("").is_err());
+        assert!(CodeAlphabet::new("ABA").is_err());
+        assert!(CodeAlphabet::new("A B").is_err());
+        assert!(CodeAlphabet::new("AÉ").is_err());
+    }
+
+    #[test]
+    fn totp_window() {
+        let hex_key = hex::encode("12345678901234567890");
+        let window = compute_totp_window(&hex_key, 59, 8, HmacHash::SHA1, 30).unwrap();
+        assert_eq!(
+            window.previous,
+            TimedCode {
+                code: "84755224".to_string(),
+                valid_from: 0,
+                valid_until: 30
+            }
+        );
+        assert_eq!(
+            window.current,
+            TimedCode {
+                code: "94287082".to_string(),
+                valid_from: 30,
+                valid_until: 60
+            }
+        );
+        assert_eq!(
+            window.next,
+            TimedCode {
+                code: "37359152".to_string(),
+                valid_from: 60,
+                valid_until: 90
+            }
+        );
+        // The current code always matches compute_totp
+        let window = compute_totp_window(&hex_key, 1111111109, 8, HmacHash::SHA1, 30).unwrap();
+        assert_eq!(window.previous.code, "89731029");
+        assert_eq!(window.current.code, "07081804");
+        assert_eq!(window.next.code, "14050471");
+        assert_eq!(
+            window.current.valid_until - 1111111109,
+            i64::from(compute_otp_lifetime(1111111109, 30).unwrap())
+        );
+        assert!(matches!(
+            compute_totp_window(&hex_key, 59, 8, HmacHash::SHA1, 0),
+            Err(TokenError::InvalidPeriod(0))
+        ));
+    }
+
+    #[test]
+    fn totp_window_start_time() {
+        let generator = OtpGenerator::new(b"12345678901234567890", HmacHash::SHA1, 6, 30)
+            .unwrap()
+            .with_start_time(-30);
+        let window = generator.totp_window(0);
+        assert_eq!(window.previous.code, "755224");
+        assert_eq!(window.previous.valid_from, -30);
+        assert_eq!(window.current.code, "287082");
+        assert_eq!(window.current.valid_from, 0);
+        assert_eq!(window.next.valid_until, 60);
+        for time in [-31, -1, 0, 29, 1111111109] {
+            let window = generator.totp_window(time);
+            let lifetime = compute_otp_lifetime(time + 30, 30).unwrap();
+            assert_eq!(generator.remaining_seconds(time), lifetime);
+            assert_eq!(window.current.valid_until, time + i64::from(lifetime));
+        }
+        // Pre-epoch times count down into negative timesteps
+        let window = OtpGenerator::new(b"12345678901234567890", HmacHash::SHA1, 6, 30)
+            .unwrap()
+            .totp_window(-1);
+        assert_eq!(window.current.code, "094451");
+        assert_eq!(window.current.valid_from, -30);
+        assert_eq!(window.next.code, "755224");
+    }
+
+    #[test]
+    fn byte_api() {
+        let key = b"12345678901234567890";
+        assert_eq!(
+            Ok(287082),
+            compute_hotp_bytes(key, 1u64.to_be_bytes(), 6, HmacHash::SHA1)
+        );
+        assert_eq!(
+            Ok(7081804),
+            compute_totp_bytes(key, 1111111109, 8, HmacHash::SHA1, 30)
+        );
+        assert_eq!("07081804", format_code(7081804, 8));
+    }
+
+    #[test]
+    fn generator_rfc_vectors() {
+        let generator = OtpGenerator::new(b"12345678901234567890", HmacHash::SHA1, 8, 30).unwrap();
+        let expected_otp_list = [
+            "94287082", "07081804", "14050471", "89005924", "69279037", "65353130",
+        ];
+        for (time, otp) in TOTP_TIME_LIST.into_iter().zip(expected_otp_list) {
+            assert_eq!(otp, generator.totp(time));
+        }
+        assert_eq!(generator.timestep(59), 1);
+        assert_eq!(generator.hotp_value(1), 94287082);
+    }
+
+    #[test]
+    fn generator_start_time() {
+        let generator = OtpGenerator::new(b"12345678901234567890", HmacHash::SHA1, 8, 30)
+            .unwrap()
+            .with_start_time(1000);
+        assert_eq!(generator.start_time(), 1000);
+        assert_eq!(generator.timestep(1059), 1);
+        assert_eq!(generator.totp(1059), "94287082");
+        assert_eq!(generator.timestep(999), -1);
+        assert_eq!(generator.remaining_seconds(1059), 1);
+        assert_eq!(generator.remaining_seconds(999), 1);
+        assert_eq!(generator.remaining_seconds(1000), 30);
+    }
+
+    #[test]
+    fn generator_with_clock() {
+        use crate::token::clock::{FixedClock, OffsetClock};
+
+        let generator = OtpGenerator::new(b"12345678901234567890", HmacHash::SHA1, 8, 30).unwrap();
+        assert_eq!(generator.totp_now(&FixedClock::new(1111111109)), "07081804");
+        let skewed = OffsetClock::new(FixedClock::new(1111111079), 30);
+        assert_eq!(generator.totp_now(&skewed), "07081804");
+    }
+
+    #[test]
+    fn generator_invalid_parameters() {
+        let key = b"12345678901234567890";
+        assert!(matches!(
+            OtpGenerator::new(key, HmacHash::SHA1, 11, 30),
+            Err(TokenError::UnsupportedDigits(11))
+        ));
+        assert!(matches!(
+            OtpGenerator::new(key, HmacHash::SHA1, 6, 0),
+            Err(TokenError::InvalidPeriod(0))
+        ));
+        assert!(matches!(
+            OtpGenerator::new(b"", HmacHash::SHA1, 6, 30),
+            Err(TokenError::EmptyKey)
+        ));
+    }
+
+    #[test]
+    fn unsupported_digits() {
+        let hex_key = hex::encode("12345678901234567890");
+        assert_eq!(
+            Err(TokenError::UnsupportedDigits(0)),
+            compute_hotp(&hex_key, 0, 0, HmacHash::SHA1)
+        );
+        assert_eq!(
+            Err(TokenError::UnsupportedDigits(11)),
+            compute_hotp(&hex_key, 0, 11, HmacHash::SHA1)
+        );
+        assert_eq!(
+            "520489",
+            compute_hotp(&hex_key, 9, 6, HmacHash::SHA1).unwrap()
+        );
+        assert_eq!(
+            "645520489",
+            compute_hotp(&hex_key, 9, 9, HmacHash::SHA1).unwrap()
+        );
+    }
+
+    #[test]
+    fn ten_digits() {
+        let hex_key = hex::encode("12345678901234567890");
+        // The whole 31 bit truncated value, RFC 4226 appendix D
+        assert_eq!(
+            "1284755224",
+            compute_hotp(&hex_key, 0, 10, HmacHash::SHA1).unwrap()
+        );
+        assert_eq!(
+            "0645520489",
+            compute_hotp(&hex_key, 9, 10, HmacHash::SHA1).unwrap()
+        );
+        assert_eq!(
+            "0907081804",
+            compute_totp(&hex_key, 1111111109, 10, HmacHash::SHA1, 30).unwrap()
+        );
+        let generator = OtpGenerator::new(b"12345678901234567890", HmacHash::SHA1, 10, 30).unwrap();
+        assert_eq!(generator.hotp_value(1), 1094287082);
+        assert_eq!(generator.hotp(1), "1094287082");
+    }
+
+    #[test]
+    fn invalid_period() {
+        let hex_key = hex::encode("12345678901234567890");
+        assert_eq!(
+            Err(TokenError::InvalidPeriod(0)),
+            compute_totp(&hex_key, 59, 8, HmacHash::SHA1, 0)
+        );
+        assert_eq!(
+            Err(TokenError::InvalidPeriod(0)),
+            compute_otp_lifetime(59, 0)
+        );
+        assert_eq!(Ok(1), compute_otp_lifetime(59, 30));
+    }
+
+    #[test]
+    fn long_periods() {
+        let hex_key = hex::encode("12345678901234567890");
+        assert_eq!(
+            compute_totp(&hex_key, 3600 * 300 + 5, 6, HmacHash::SHA1, 3600).unwrap(),
+            compute_hotp(&hex_key, 300, 6, HmacHash::SHA1).unwrap()
+        );
+        assert_eq!(Ok(295), compute_otp_lifetime(5, 300));
+        assert_eq!(Ok(3595), compute_otp_lifetime(3600 * 300 + 5, 3600));
+    }
+
+    #[test]
+    fn pre_epoch_lifetime() {
+        assert_eq!(Ok(1), compute_otp_lifetime(-1, 30));
+        assert_eq!(Ok(30), compute_otp_lifetime(-30, 30));
+        assert_eq!(Ok(29), compute_otp_lifetime(-59, 30));
+        // The code for -1 belongs to timestep -1, which ends at 0
+        let hex_key = hex::encode("12345678901234567890");
+        assert_eq!(
+            compute_totp(&hex_key, -1, 6, HmacHash::SHA1, 30).unwrap(),
+            compute_hotp(&hex_key, -1, 6, HmacHash::SHA1).unwrap()
+        );
+    }
+
+    #[test]
+    fn invalid_key() {
+        assert_eq!(
+            Err(TokenError::EmptyKey),
+            compute_hotp("", 0, 6, HmacHash::SHA1)
+        );
+        assert!(matches!(
+            compute_totp("GEZDGNBV", 59, 6, HmacHash::SHA1, 30),
+            Err(TokenError::InvalidKeyEncoding(_))
+        ));
+    }
 }
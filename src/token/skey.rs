@@ -0,0 +1,649 @@
+use crate::token::TokenError;
+use byteorder::{BigEndian, ByteOrder, LittleEndian};
+use md5::{Digest, Md5};
+use ring::{constant_time, digest};
+use std::fmt;
+use std::str::FromStr;
+
+// RFC 2289 section 6, the first 571 words of the dictionary
+// are one to three letters long, the rest are four letters long
+const SHORT_WORDS: usize = 571;
+const MAX_SEED_LENGTH: usize = 16;
+const MIN_PASSPHRASE_LENGTH: usize = 10;
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum SkeyHash {
+    MD5,
+    SHA1,
+}
+
+impl fmt::Display for SkeyHash {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SkeyHash::MD5 => write!(f, "md5"),
+            SkeyHash::SHA1 => write!(f, "sha1"),
+        }
+    }
+}
+
+/// An RFC 2289 challenge as printed by the host, e.g. `otp-md5 499 ke1234`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SkeyChallenge {
+    hash: SkeyHash,
+    sequence: u32,
+    seed: String,
+}
+
+fn check_seed(seed: &str) -> Result<(), TokenError> {
+    if seed.is_empty()
+        || seed.len() > MAX_SEED_LENGTH
+        || !seed.chars().all(|c| c.is_ascii_alphanumeric())
+    {
+        return Err(TokenError::InvalidSkeyInput(format!(
+            "seed must be 1 to {MAX_SEED_LENGTH} alphanumeric characters"
+        )));
+    }
+    Ok(())
+}
+
+impl SkeyChallenge {
+    pub fn new(hash: SkeyHash, sequence: u32, seed: &str) -> Result<Self, TokenError> {
+        check_seed(seed)?;
+        Ok(SkeyChallenge {
+            hash,
+            sequence,
+            // Seeds are case insensitive, RFC 2289 section 6
+            seed: seed.to_ascii_lowercase(),
+        })
+    }
+
+    pub fn hash(&self) -> SkeyHash {
+        self.hash
+    }
+
+    pub fn sequence(&self) -> u32 {
+        self.sequence
+    }
+
+    pub fn seed(&self) -> &str {
+        &self.seed
+    }
+
+    /// The challenge for the next login, one step down the chain.
+    /// Returns `None` once sequence 0 has been used.
+    pub fn next(&self) -> Option<Self> {
+        Some(SkeyChallenge {
+            sequence: self.sequence.checked_sub(1)?,
+            ..self.clone()
+        })
+    }
+
+    /// The one-time password for this challenge.
+    pub fn response(&self, passphrase: &str) -> Result<[u8; 8], TokenError> {
+        compute_skey(self.hash, passphrase, &self.seed, self.sequence)
+    }
+}
+
+impl FromStr for SkeyChallenge {
+    type Err = TokenError;
+    fn from_str(s: &str) -> Result<Self, TokenError> {
+        let invalid = || TokenError::InvalidSkeyInput(format!("malformed challenge: {s}"));
+        let parts = s.split_whitespace().collect::<Vec<_>>();
+        let [algorithm, sequence, seed] = parts[..] else {
+            return Err(invalid());
+        };
+        let hash = match algorithm.to_ascii_lowercase().as_str() {
+            "otp-md5" => SkeyHash::MD5,
+            "otp-sha1" => SkeyHash::SHA1,
+            _ => return Err(invalid()),
+        };
+        let sequence = sequence.parse().map_err(|_| invalid())?;
+        SkeyChallenge::new(hash, sequence, seed)
+    }
+}
+
+impl fmt::Display for SkeyChallenge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "otp-{} {} {}", self.hash, self.sequence, self.seed)
+    }
+}
+
+/// Hashes `data` and folds the digest down to 64 bits, RFC 2289 appendix A.
+fn fold(hash: SkeyHash, data: &[u8]) -> [u8; 8] {
+    let mut folded = [0u8; 8];
+    match hash {
+        SkeyHash::MD5 => {
+            let digest = Md5::digest(data);
+            let (low, high) = digest.split_at(8);
+            for (out, (a, b)) in folded.iter_mut().zip(low.iter().zip(high)) {
+                *out = a ^ b;
+            }
+        }
+        SkeyHash::SHA1 => {
+            let digest = digest::digest(&digest::SHA1_FOR_LEGACY_USE_ONLY, data);
+            let mut words = [0u32; 5];
+            BigEndian::read_u32_into(digest.as_ref(), &mut words);
+            // The folded words are written out in little endian order
+            LittleEndian::write_u32(&mut folded[..4], words[0] ^ words[2] ^ words[4]);
+            LittleEndian::write_u32(&mut folded[4..], words[1] ^ words[3]);
+        }
+    }
+    folded
+}
+
+/// Computes the one-time password for `sequence`: the seed and passphrase
+/// are hashed once, then the result is hashed `sequence` more times.
+pub fn compute_skey(
+    hash: SkeyHash,
+    passphrase: &str,
+    seed: &str,
+    sequence: u32,
+) -> Result<[u8; 8], TokenError> {
+    check_seed(seed)?;
+    if passphrase.len() < MIN_PASSPHRASE_LENGTH {
+        return Err(TokenError::InvalidSkeyInput(format!(
+            "passphrase must be at least {MIN_PASSPHRASE_LENGTH} characters"
+        )));
+    }
+    let mut otp = fold(
+        hash,
+        format!("{}{passphrase}", seed.to_ascii_lowercase()).as_bytes(),
+    );
+    for _ in 0..sequence {
+        otp = fold(hash, &otp);
+    }
+    Ok(otp)
+}
+
+pub fn format_hex(otp: &[u8; 8]) -> String {
+    hex::encode_upper(otp)
+}
+
+// Sum of the 2 bit pairs of the password
+fn checksum(value: u64) -> u64 {
+    (0..32).map(|i| (value >> (2 * i)) & 3).sum::<u64>() & 3
+}
+
+/// Encodes a one-time password as six dictionary words, RFC 2289 appendix D.
+pub fn format_words(otp: &[u8; 8]) -> String {
+    let value = BigEndian::read_u64(otp);
+    let bits = u128::from(value) << 2 | u128::from(checksum(value));
+    (0..6)
+        .map(|i| WORDS[(bits >> (11 * (5 - i)) & 0x7ff) as usize])
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+fn word_index(word: &str) -> Option<usize> {
+    let word = word.to_ascii_uppercase();
+    let (short, long) = WORDS.split_at(SHORT_WORDS);
+    match word.len() {
+        1..=3 => short.binary_search(&word.as_str()).ok(),
+        4 => long
+            .binary_search(&word.as_str())
+            .ok()
+            .map(|i| i + SHORT_WORDS),
+        _ => None,
+    }
+}
+
+/// Decodes six dictionary words back into a one-time password.
+/// Words are case insensitive and the checksum is checked.
+pub fn parse_words(words: &str) -> Result<[u8; 8], TokenError> {
+    let words = words.split_whitespace().collect::<Vec<_>>();
+    if words.len() != 6 {
+        return Err(TokenError::InvalidSkeyInput(format!(
+            "expected six words, got {}",
+            words.len()
+        )));
+    }
+    let mut bits = 0u128;
+    for word in words {
+        let index = word_index(word)
+            .ok_or_else(|| TokenError::InvalidSkeyInput(format!("unknown word: {word}")))?;
+        bits = bits << 11 | index as u128;
+    }
+    let value = (bits >> 2) as u64;
+    if checksum(value) != (bits & 3) as u64 {
+        return Err(TokenError::InvalidSkeyInput("bad checksum".to_string()));
+    }
+    Ok(value.to_be_bytes())
+}
+
+/// Parses a response in either hex or six word format.
+/// The `hex:` and `word:` prefixes of RFC 2243 are accepted.
+pub fn parse_response(response: &str) -> Result<[u8; 8], TokenError> {
+    let response = response.trim();
+    if let Some(words) = response.strip_prefix("word:") {
+        return parse_words(words);
+    }
+    let hex_digits = response
+        .strip_prefix("hex:")
+        .unwrap_or(response)
+        .split_whitespace()
+        .collect::<String>();
+    let mut otp = [0u8; 8];
+    match hex::decode_to_slice(&hex_digits, &mut otp) {
+        Ok(()) => Ok(otp),
+        Err(_) if response.starts_with("hex:") => Err(TokenError::InvalidSkeyInput(
+            "expected 16 hex digits".to_string(),
+        )),
+        Err(_) => parse_words(response),
+    }
+}
+
+/// Checks a response against the last one-time password the host accepted.
+/// The response is valid if hashing it once gives the stored password.
+/// Returns the password to store for the next login.
+pub fn verify_skey(
+    hash: SkeyHash,
+    response: &str,
+    previous: &[u8; 8],
+) -> Result<Option<[u8; 8]>, TokenError> {
+    let otp = parse_response(response)?;
+    let matched = constant_time::verify_slices_are_equal(&fold(hash, &otp), previous).is_ok();
+    Ok(matched.then_some(otp))
+}
+
+static WORDS: [&str; 2048] = [
+    "A", "ABE", "ACE", "ACT", "AD", "ADA", "ADD", "AGO", "AID", "AIM", "AIR", "ALL", "ALP", "AM",
+    "AMY", "AN", "ANA", "AND", "ANN", "ANT", "ANY", "APE", "APS", "APT", "ARC", "ARE", "ARK",
+    "ARM", "ART", "AS", "ASH", "ASK", "AT", "ATE", "AUG", "AUK", "AVE", "AWE", "AWK", "AWL", "AWN",
+    "AX", "AYE", "BAD", "BAG", "BAH", "BAM", "BAN", "BAR", "BAT", "BAY", "BE", "BED", "BEE", "BEG",
+    "BEN", "BET", "BEY", "BIB", "BID", "BIG", "BIN", "BIT", "BOB", "BOG", "BON", "BOO", "BOP",
+    "BOW", "BOY", "BUB", "BUD", "BUG", "BUM", "BUN", "BUS", "BUT", "BUY", "BY", "BYE", "CAB",
+    "CAL", "CAM", "CAN", "CAP", "CAR", "CAT", "CAW", "COD", "COG", "COL", "CON", "COO", "COP",
+    "COT", "COW", "COY", "CRY", "CUB", "CUE", "CUP", "CUR", "CUT", "DAB", "DAD", "DAM", "DAN",
+    "DAR", "DAY", "DEE", "DEL", "DEN", "DES", "DEW", "DID", "DIE", "DIG", "DIN", "DIP", "DO",
+    "DOE", "DOG", "DON", "DOT", "DOW", "DRY", "DUB", "DUD", "DUE", "DUG", "DUN", "EAR", "EAT",
+    "ED", "EEL", "EGG", "EGO", "ELI", "ELK", "ELM", "ELY", "EM", "END", "EST", "ETC", "EVA", "EVE",
+    "EWE", "EYE", "FAD", "FAN", "FAR", "FAT", "FAY", "FED", "FEE", "FEW", "FIB", "FIG", "FIN",
+    "FIR", "FIT", "FLO", "FLY", "FOE", "FOG", "FOR", "FRY", "FUM", "FUN", "FUR", "GAB", "GAD",
+    "GAG", "GAL", "GAM", "GAP", "GAS", "GAY", "GEE", "GEL", "GEM", "GET", "GIG", "GIL", "GIN",
+    "GO", "GOT", "GUM", "GUN", "GUS", "GUT", "GUY", "GYM", "GYP", "HA", "HAD", "HAL", "HAM", "HAN",
+    "HAP", "HAS", "HAT", "HAW", "HAY", "HE", "HEM", "HEN", "HER", "HEW", "HEY", "HI", "HID", "HIM",
+    "HIP", "HIS", "HIT", "HO", "HOB", "HOC", "HOE", "HOG", "HOP", "HOT", "HOW", "HUB", "HUE",
+    "HUG", "HUH", "HUM", "HUT", "I", "ICY", "IDA", "IF", "IKE", "ILL", "INK", "INN", "IO", "ION",
+    "IQ", "IRA", "IRE", "IRK", "IS", "IT", "ITS", "IVY", "JAB", "JAG", "JAM", "JAN", "JAR", "JAW",
+    "JAY", "JET", "JIG", "JIM", "JO", "JOB", "JOE", "JOG", "JOT", "JOY", "JUG", "JUT", "KAY",
+    "KEG", "KEN", "KEY", "KID", "KIM", "KIN", "KIT", "LA", "LAB", "LAC", "LAD", "LAG", "LAM",
+    "LAP", "LAW", "LAY", "LEA", "LED", "LEE", "LEG", "LEN", "LEO", "LET", "LEW", "LID", "LIE",
+    "LIN", "LIP", "LIT", "LO", "LOB", "LOG", "LOP", "LOS", "LOT", "LOU", "LOW", "LOY", "LUG",
+    "LYE", "MA", "MAC", "MAD", "MAE", "MAN", "MAO", "MAP", "MAT", "MAW", "MAY", "ME", "MEG", "MEL",
+    "MEN", "MET", "MEW", "MID", "MIN", "MIT", "MOB", "MOD", "MOE", "MOO", "MOP", "MOS", "MOT",
+    "MOW", "MUD", "MUG", "MUM", "MY", "NAB", "NAG", "NAN", "NAP", "NAT", "NAY", "NE", "NED", "NEE",
+    "NET", "NEW", "NIB", "NIL", "NIP", "NIT", "NO", "NOB", "NOD", "NON", "NOR", "NOT", "NOV",
+    "NOW", "NU", "NUN", "NUT", "O", "OAF", "OAK", "OAR", "OAT", "ODD", "ODE", "OF", "OFF", "OFT",
+    "OH", "OIL", "OK", "OLD", "ON", "ONE", "OR", "ORB", "ORE", "ORR", "OS", "OTT", "OUR", "OUT",
+    "OVA", "OW", "OWE", "OWL", "OWN", "OX", "PA", "PAD", "PAL", "PAM", "PAN", "PAP", "PAR", "PAT",
+    "PAW", "PAY", "PEA", "PEG", "PEN", "PEP", "PER", "PET", "PEW", "PHI", "PI", "PIE", "PIN",
+    "PIT", "PLY", "PO", "POD", "POE", "POP", "POT", "POW", "PRO", "PRY", "PUB", "PUG", "PUN",
+    "PUP", "PUT", "QUO", "RAG", "RAM", "RAN", "RAP", "RAT", "RAW", "RAY", "REB", "RED", "REP",
+    "RET", "RIB", "RID", "RIG", "RIM", "RIO", "RIP", "ROB", "ROD", "ROE", "RON", "ROT", "ROW",
+    "ROY", "RUB", "RUE", "RUG", "RUM", "RUN", "RYE", "SAC", "SAD", "SAG", "SAL", "SAM", "SAN",
+    "SAP", "SAT", "SAW", "SAY", "SEA", "SEC", "SEE", "SEN", "SET", "SEW", "SHE", "SHY", "SIN",
+    "SIP", "SIR", "SIS", "SIT", "SKI", "SKY", "SLY", "SO", "SOB", "SOD", "SON", "SOP", "SOW",
+    "SOY", "SPA", "SPY", "SUB", "SUD", "SUE", "SUM", "SUN", "SUP", "TAB", "TAD", "TAG", "TAN",
+    "TAP", "TAR", "TEA", "TED", "TEE", "TEN", "THE", "THY", "TIC", "TIE", "TIM", "TIN", "TIP",
+    "TO", "TOE", "TOG", "TOM", "TON", "TOO", "TOP", "TOW", "TOY", "TRY", "TUB", "TUG", "TUM",
+    "TUN", "TWO", "UN", "UP", "US", "USE", "VAN", "VAT", "VET", "VIE", "WAD", "WAG", "WAR", "WAS",
+    "WAY", "WE", "WEB", "WED", "WEE", "WET", "WHO", "WHY", "WIN", "WIT", "WOK", "WON", "WOO",
+    "WOW", "WRY", "WU", "YAM", "YAP", "YAW", "YE", "YEA", "YES", "YET", "YOU", "ABED", "ABEL",
+    "ABET", "ABLE", "ABUT", "ACHE", "ACID", "ACME", "ACRE", "ACTA", "ACTS", "ADAM", "ADDS", "ADEN",
+    "AFAR", "AFRO", "AGEE", "AHEM", "AHOY", "AIDA", "AIDE", "AIDS", "AIRY", "AJAR", "AKIN", "ALAN",
+    "ALEC", "ALGA", "ALIA", "ALLY", "ALMA", "ALOE", "ALSO", "ALTO", "ALUM", "ALVA", "AMEN", "AMES",
+    "AMID", "AMMO", "AMOK", "AMOS", "AMRA", "ANDY", "ANEW", "ANNA", "ANNE", "ANTE", "ANTI", "AQUA",
+    "ARAB", "ARCH", "AREA", "ARGO", "ARID", "ARMY", "ARTS", "ARTY", "ASIA", "ASKS", "ATOM", "AUNT",
+    "AURA", "AUTO", "AVER", "AVID", "AVIS", "AVON", "AVOW", "AWAY", "AWRY", "BABE", "BABY", "BACH",
+    "BACK", "BADE", "BAIL", "BAIT", "BAKE", "BALD", "BALE", "BALI", "BALK", "BALL", "BALM", "BAND",
+    "BANE", "BANG", "BANK", "BARB", "BARD", "BARE", "BARK", "BARN", "BARR", "BASE", "BASH", "BASK",
+    "BASS", "BATE", "BATH", "BAWD", "BAWL", "BEAD", "BEAK", "BEAM", "BEAN", "BEAR", "BEAT", "BEAU",
+    "BECK", "BEEF", "BEEN", "BEER", "BEET", "BELA", "BELL", "BELT", "BEND", "BENT", "BERG", "BERN",
+    "BERT", "BESS", "BEST", "BETA", "BETH", "BHOY", "BIAS", "BIDE", "BIEN", "BILE", "BILK", "BILL",
+    "BIND", "BING", "BIRD", "BITE", "BITS", "BLAB", "BLAT", "BLED", "BLEW", "BLOB", "BLOC", "BLOT",
+    "BLOW", "BLUE", "BLUM", "BLUR", "BOAR", "BOAT", "BOCA", "BOCK", "BODE", "BODY", "BOGY", "BOHR",
+    "BOIL", "BOLD", "BOLO", "BOLT", "BOMB", "BONA", "BOND", "BONE", "BONG", "BONN", "BONY", "BOOK",
+    "BOOM", "BOON", "BOOT", "BORE", "BORG", "BORN", "BOSE", "BOSS", "BOTH", "BOUT", "BOWL", "BOYD",
+    "BRAD", "BRAE", "BRAG", "BRAN", "BRAY", "BRED", "BREW", "BRIG", "BRIM", "BROW", "BUCK", "BUDD",
+    "BUFF", "BULB", "BULK", "BULL", "BUNK", "BUNT", "BUOY", "BURG", "BURL", "BURN", "BURR", "BURT",
+    "BURY", "BUSH", "BUSS", "BUST", "BUSY", "BYTE", "CADY", "CAFE", "CAGE", "CAIN", "CAKE", "CALF",
+    "CALL", "CALM", "CAME", "CANE", "CANT", "CARD", "CARE", "CARL", "CARR", "CART", "CASE", "CASH",
+    "CASK", "CAST", "CAVE", "CEIL", "CELL", "CENT", "CERN", "CHAD", "CHAR", "CHAT", "CHAW", "CHEF",
+    "CHEN", "CHEW", "CHIC", "CHIN", "CHOU", "CHOW", "CHUB", "CHUG", "CHUM", "CITE", "CITY", "CLAD",
+    "CLAM", "CLAN", "CLAW", "CLAY", "CLOD", "CLOG", "CLOT", "CLUB", "CLUE", "COAL", "COAT", "COCA",
+    "COCK", "COCO", "CODA", "CODE", "CODY", "COED", "COIL", "COIN", "COKE", "COLA", "COLD", "COLT",
+    "COMA", "COMB", "COME", "COOK", "COOL", "COON", "COOT", "CORD", "CORE", "CORK", "CORN", "COST",
+    "COVE", "COWL", "CRAB", "CRAG", "CRAM", "CRAY", "CREW", "CRIB", "CROW", "CRUD", "CUBA", "CUBE",
+    "CUFF", "CULL", "CULT", "CUNY", "CURB", "CURD", "CURE", "CURL", "CURT", "CUTS", "DADE", "DALE",
+    "DAME", "DANA", "DANE", "DANG", "DANK", "DARE", "DARK", "DARN", "DART", "DASH", "DATA", "DATE",
+    "DAVE", "DAVY", "DAWN", "DAYS", "DEAD", "DEAF", "DEAL", "DEAN", "DEAR", "DEBT", "DECK", "DEED",
+    "DEEM", "DEER", "DEFT", "DEFY", "DELL", "DENT", "DENY", "DESK", "DIAL", "DICE", "DIED", "DIET",
+    "DIME", "DINE", "DING", "DINT", "DIRE", "DIRT", "DISC", "DISH", "DISK", "DIVE", "DOCK", "DOES",
+    "DOLE", "DOLL", "DOLT", "DOME", "DONE", "DOOM", "DOOR", "DORA", "DOSE", "DOTE", "DOUG", "DOUR",
+    "DOVE", "DOWN", "DRAB", "DRAG", "DRAM", "DRAW", "DREW", "DRUB", "DRUG", "DRUM", "DUAL", "DUCK",
+    "DUCT", "DUEL", "DUET", "DUKE", "DULL", "DUMB", "DUNE", "DUNK", "DUSK", "DUST", "DUTY", "EACH",
+    "EARL", "EARN", "EASE", "EAST", "EASY", "EBEN", "ECHO", "EDDY", "EDEN", "EDGE", "EDGY", "EDIT",
+    "EDNA", "EGAN", "ELAN", "ELBA", "ELLA", "ELSE", "EMIL", "EMIT", "EMMA", "ENDS", "ERIC", "EROS",
+    "EVEN", "EVER", "EVIL", "EYED", "FACE", "FACT", "FADE", "FAIL", "FAIN", "FAIR", "FAKE", "FALL",
+    "FAME", "FANG", "FARM", "FAST", "FATE", "FAWN", "FEAR", "FEAT", "FEED", "FEEL", "FEET", "FELL",
+    "FELT", "FEND", "FERN", "FEST", "FEUD", "FIEF", "FIGS", "FILE", "FILL", "FILM", "FIND", "FINE",
+    "FINK", "FIRE", "FIRM", "FISH", "FISK", "FIST", "FITS", "FIVE", "FLAG", "FLAK", "FLAM", "FLAT",
+    "FLAW", "FLEA", "FLED", "FLEW", "FLIT", "FLOC", "FLOG", "FLOW", "FLUB", "FLUE", "FOAL", "FOAM",
+    "FOGY", "FOIL", "FOLD", "FOLK", "FOND", "FONT", "FOOD", "FOOL", "FOOT", "FORD", "FORE", "FORK",
+    "FORM", "FORT", "FOSS", "FOUL", "FOUR", "FOWL", "FRAU", "FRAY", "FRED", "FREE", "FRET", "FREY",
+    "FROG", "FROM", "FUEL", "FULL", "FUME", "FUND", "FUNK", "FURY", "FUSE", "FUSS", "GAFF", "GAGE",
+    "GAIL", "GAIN", "GAIT", "GALA", "GALE", "GALL", "GALT", "GAME", "GANG", "GARB", "GARY", "GASH",
+    "GATE", "GAUL", "GAUR", "GAVE", "GAWK", "GEAR", "GELD", "GENE", "GENT", "GERM", "GETS", "GIBE",
+    "GIFT", "GILD", "GILL", "GILT", "GINA", "GIRD", "GIRL", "GIST", "GIVE", "GLAD", "GLEE", "GLEN",
+    "GLIB", "GLOB", "GLOM", "GLOW", "GLUE", "GLUM", "GLUT", "GOAD", "GOAL", "GOAT", "GOER", "GOES",
+    "GOLD", "GOLF", "GONE", "GONG", "GOOD", "GOOF", "GORE", "GORY", "GOSH", "GOUT", "GOWN", "GRAB",
+    "GRAD", "GRAY", "GREG", "GREW", "GREY", "GRID", "GRIM", "GRIN", "GRIT", "GROW", "GRUB", "GULF",
+    "GULL", "GUNK", "GURU", "GUSH", "GUST", "GWEN", "GWYN", "HAAG", "HAAS", "HACK", "HAIL", "HAIR",
+    "HALE", "HALF", "HALL", "HALO", "HALT", "HAND", "HANG", "HANK", "HANS", "HARD", "HARK", "HARM",
+    "HART", "HASH", "HAST", "HATE", "HATH", "HAUL", "HAVE", "HAWK", "HAYS", "HEAD", "HEAL", "HEAR",
+    "HEAT", "HEBE", "HECK", "HEED", "HEEL", "HEFT", "HELD", "HELL", "HELM", "HERB", "HERD", "HERE",
+    "HERO", "HERS", "HESS", "HEWN", "HICK", "HIDE", "HIGH", "HIKE", "HILL", "HILT", "HIND", "HINT",
+    "HIRE", "HISS", "HIVE", "HOBO", "HOCK", "HOFF", "HOLD", "HOLE", "HOLM", "HOLT", "HOME", "HONE",
+    "HONK", "HOOD", "HOOF", "HOOK", "HOOT", "HORN", "HOSE", "HOST", "HOUR", "HOVE", "HOWE", "HOWL",
+    "HOYT", "HUCK", "HUED", "HUFF", "HUGE", "HUGH", "HUGO", "HULK", "HULL", "HUNK", "HUNT", "HURD",
+    "HURL", "HURT", "HUSH", "HYDE", "HYMN", "IBIS", "ICON", "IDEA", "IDLE", "IFFY", "INCA", "INCH",
+    "INTO", "IONS", "IOTA", "IOWA", "IRIS", "IRMA", "IRON", "ISLE", "ITCH", "ITEM", "IVAN", "JACK",
+    "JADE", "JAIL", "JAKE", "JANE", "JAVA", "JEAN", "JEFF", "JERK", "JESS", "JEST", "JIBE", "JILL",
+    "JILT", "JIVE", "JOAN", "JOBS", "JOCK", "JOEL", "JOEY", "JOHN", "JOIN", "JOKE", "JOLT", "JOVE",
+    "JUDD", "JUDE", "JUDO", "JUDY", "JUJU", "JUKE", "JULY", "JUNE", "JUNK", "JUNO", "JURY", "JUST",
+    "JUTE", "KAHN", "KALE", "KANE", "KANT", "KARL", "KATE", "KEEL", "KEEN", "KENO", "KENT", "KERN",
+    "KERR", "KEYS", "KICK", "KILL", "KIND", "KING", "KIRK", "KISS", "KITE", "KLAN", "KNEE", "KNEW",
+    "KNIT", "KNOB", "KNOT", "KNOW", "KOCH", "KONG", "KUDO", "KURD", "KURT", "KYLE", "LACE", "LACK",
+    "LACY", "LADY", "LAID", "LAIN", "LAIR", "LAKE", "LAMB", "LAME", "LAND", "LANE", "LANG", "LARD",
+    "LARK", "LASS", "LAST", "LATE", "LAUD", "LAVA", "LAWN", "LAWS", "LAYS", "LEAD", "LEAF", "LEAK",
+    "LEAN", "LEAR", "LEEK", "LEER", "LEFT", "LEND", "LENS", "LENT", "LEON", "LESK", "LESS", "LEST",
+    "LETS", "LIAR", "LICE", "LICK", "LIED", "LIEN", "LIES", "LIEU", "LIFE", "LIFT", "LIKE", "LILA",
+    "LILT", "LILY", "LIMA", "LIMB", "LIME", "LIND", "LINE", "LINK", "LINT", "LION", "LISA", "LIST",
+    "LIVE", "LOAD", "LOAF", "LOAM", "LOAN", "LOCK", "LOFT", "LOGE", "LOIS", "LOLA", "LONE", "LONG",
+    "LOOK", "LOON", "LOOT", "LORD", "LORE", "LOSE", "LOSS", "LOST", "LOUD", "LOVE", "LOWE", "LUCK",
+    "LUCY", "LUGE", "LUKE", "LULU", "LUND", "LUNG", "LURA", "LURE", "LURK", "LUSH", "LUST", "LYLE",
+    "LYNN", "LYON", "LYRA", "MACE", "MADE", "MAGI", "MAID", "MAIL", "MAIN", "MAKE", "MALE", "MALI",
+    "MALL", "MALT", "MANA", "MANN", "MANY", "MARC", "MARE", "MARK", "MARS", "MART", "MARY", "MASH",
+    "MASK", "MASS", "MAST", "MATE", "MATH", "MAUL", "MAYO", "MEAD", "MEAL", "MEAN", "MEAT", "MEEK",
+    "MEET", "MELD", "MELT", "MEMO", "MEND", "MENU", "MERT", "MESH", "MESS", "MICE", "MIKE", "MILD",
+    "MILE", "MILK", "MILL", "MILT", "MIMI", "MIND", "MINE", "MINI", "MINK", "MINT", "MIRE", "MISS",
+    "MIST", "MITE", "MITT", "MOAN", "MOAT", "MOCK", "MODE", "MOLD", "MOLE", "MOLL", "MOLT", "MONA",
+    "MONK", "MONT", "MOOD", "MOON", "MOOR", "MOOT", "MORE", "MORN", "MORT", "MOSS", "MOST", "MOTH",
+    "MOVE", "MUCH", "MUCK", "MUDD", "MUFF", "MULE", "MULL", "MURK", "MUSH", "MUST", "MUTE", "MUTT",
+    "MYRA", "MYTH", "NAGY", "NAIL", "NAIR", "NAME", "NARY", "NASH", "NAVE", "NAVY", "NEAL", "NEAR",
+    "NEAT", "NECK", "NEED", "NEIL", "NELL", "NEON", "NERO", "NESS", "NEST", "NEWS", "NEWT", "NIBS",
+    "NICE", "NICK", "NILE", "NINA", "NINE", "NOAH", "NODE", "NOEL", "NOLL", "NONE", "NOOK", "NOON",
+    "NORM", "NOSE", "NOTE", "NOUN", "NOVA", "NUDE", "NULL", "NUMB", "OATH", "OBEY", "OBOE", "ODIN",
+    "OHIO", "OILY", "OINT", "OKAY", "OLAF", "OLDY", "OLGA", "OLIN", "OMAN", "OMEN", "OMIT", "ONCE",
+    "ONES", "ONLY", "ONTO", "ONUS", "ORAL", "ORGY", "OSLO", "OTIS", "OTTO", "OUCH", "OUST", "OUTS",
+    "OVAL", "OVEN", "OVER", "OWLY", "OWNS", "QUAD", "QUIT", "QUOD", "RACE", "RACK", "RACY", "RAFT",
+    "RAGE", "RAID", "RAIL", "RAIN", "RAKE", "RANK", "RANT", "RARE", "RASH", "RATE", "RAVE", "RAYS",
+    "READ", "REAL", "REAM", "REAR", "RECK", "REED", "REEF", "REEK", "REEL", "REID", "REIN", "RENA",
+    "REND", "RENT", "REST", "RICE", "RICH", "RICK", "RIDE", "RIFT", "RILL", "RIME", "RING", "RINK",
+    "RISE", "RISK", "RITE", "ROAD", "ROAM", "ROAR", "ROBE", "ROCK", "RODE", "ROIL", "ROLL", "ROME",
+    "ROOD", "ROOF", "ROOK", "ROOM", "ROOT", "ROSA", "ROSE", "ROSS", "ROSY", "ROTH", "ROUT", "ROVE",
+    "ROWE", "ROWS", "RUBE", "RUBY", "RUDE", "RUDY", "RUIN", "RULE", "RUNG", "RUNS", "RUNT", "RUSE",
+    "RUSH", "RUSK", "RUSS", "RUST", "RUTH", "SACK", "SAFE", "SAGE", "SAID", "SAIL", "SALE", "SALK",
+    "SALT", "SAME", "SAND", "SANE", "SANG", "SANK", "SARA", "SAUL", "SAVE", "SAYS", "SCAN", "SCAR",
+    "SCAT", "SCOT", "SEAL", "SEAM", "SEAR", "SEAT", "SEED", "SEEK", "SEEM", "SEEN", "SEES", "SELF",
+    "SELL", "SEND", "SENT", "SETS", "SEWN", "SHAG", "SHAM", "SHAW", "SHAY", "SHED", "SHIM", "SHIN",
+    "SHOD", "SHOE", "SHOT", "SHOW", "SHUN", "SHUT", "SICK", "SIDE", "SIFT", "SIGH", "SIGN", "SILK",
+    "SILL", "SILO", "SILT", "SINE", "SING", "SINK", "SIRE", "SITE", "SITS", "SITU", "SKAT", "SKEW",
+    "SKID", "SKIM", "SKIN", "SKIT", "SLAB", "SLAM", "SLAT", "SLAY", "SLED", "SLEW", "SLID", "SLIM",
+    "SLIT", "SLOB", "SLOG", "SLOT", "SLOW", "SLUG", "SLUM", "SLUR", "SMOG", "SMUG", "SNAG", "SNOB",
+    "SNOW", "SNUB", "SNUG", "SOAK", "SOAR", "SOCK", "SODA", "SOFA", "SOFT", "SOIL", "SOLD", "SOME",
+    "SONG", "SOON", "SOOT", "SORE", "SORT", "SOUL", "SOUR", "SOWN", "STAB", "STAG", "STAN", "STAR",
+    "STAY", "STEM", "STEW", "STIR", "STOW", "STUB", "STUN", "SUCH", "SUDS", "SUIT", "SULK", "SUMS",
+    "SUNG", "SUNK", "SURE", "SURF", "SWAB", "SWAG", "SWAM", "SWAN", "SWAT", "SWAY", "SWIM", "SWUM",
+    "TACK", "TACT", "TAIL", "TAKE", "TALE", "TALK", "TALL", "TANK", "TASK", "TATE", "TAUT", "TEAL",
+    "TEAM", "TEAR", "TECH", "TEEM", "TEEN", "TEET", "TELL", "TEND", "TENT", "TERM", "TERN", "TESS",
+    "TEST", "THAN", "THAT", "THEE", "THEM", "THEN", "THEY", "THIN", "THIS", "THUD", "THUG", "TICK",
+    "TIDE", "TIDY", "TIED", "TIER", "TILE", "TILL", "TILT", "TIME", "TINA", "TINE", "TINT", "TINY",
+    "TIRE", "TOAD", "TOGO", "TOIL", "TOLD", "TOLL", "TONE", "TONG", "TONY", "TOOK", "TOOL", "TOOT",
+    "TORE", "TORN", "TOTE", "TOUR", "TOUT", "TOWN", "TRAG", "TRAM", "TRAY", "TREE", "TREK", "TRIG",
+    "TRIM", "TRIO", "TROD", "TROT", "TROY", "TRUE", "TUBA", "TUBE", "TUCK", "TUFT", "TUNA", "TUNE",
+    "TUNG", "TURF", "TURN", "TUSK", "TWIG", "TWIN", "TWIT", "ULAN", "UNIT", "URGE", "USED", "USER",
+    "USES", "UTAH", "VAIL", "VAIN", "VALE", "VARY", "VASE", "VAST", "VEAL", "VEDA", "VEIL", "VEIN",
+    "VEND", "VENT", "VERB", "VERY", "VETO", "VICE", "VIEW", "VINE", "VISE", "VOID", "VOLT", "VOTE",
+    "WACK", "WADE", "WAGE", "WAIL", "WAIT", "WAKE", "WALE", "WALK", "WALL", "WALT", "WAND", "WANE",
+    "WANG", "WANT", "WARD", "WARM", "WARN", "WART", "WASH", "WAST", "WATS", "WATT", "WAVE", "WAVY",
+    "WAYS", "WEAK", "WEAL", "WEAN", "WEAR", "WEED", "WEEK", "WEIR", "WELD", "WELL", "WELT", "WENT",
+    "WERE", "WERT", "WEST", "WHAM", "WHAT", "WHEE", "WHEN", "WHET", "WHOA", "WHOM", "WICK", "WIFE",
+    "WILD", "WILL", "WIND", "WINE", "WING", "WINK", "WINO", "WIRE", "WISE", "WISH", "WITH", "WOLF",
+    "WONT", "WOOD", "WOOL", "WORD", "WORE", "WORK", "WORM", "WORN", "WOVE", "WRIT", "WYNN", "YALE",
+    "YANG", "YANK", "YARD", "YARN", "YAWL", "YAWN", "YEAH", "YEAR", "YELL", "YOGA", "YOKE",
+];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // RFC 2289 appendix C: passphrase, seed, sequence, hex, six words
+    static MD5_VECTORS: [(&str, &str, u32, &str, &str); 9] = [
+        (
+            "This is a test.",
+            "TeSt",
+            0,
+            "9E876134D90499DD",
+            "INCH SEA ANNE LONG AHEM TOUR",
+        ),
+        (
+            "This is a test.",
+            "TeSt",
+            1,
+            "7965E05436F5029F",
+            "EASE OIL FUM CURE AWRY AVIS",
+        ),
+        (
+            "This is a test.",
+            "TeSt",
+            99,
+            "50FE1962C4965880",
+            "BAIL TUFT BITS GANG CHEF THY",
+        ),
+        (
+            "AbCdEfGhIjK",
+            "alpha1",
+            0,
+            "87066DD9644BF206",
+            "FULL PEW DOWN ONCE MORT ARC",
+        ),
+        (
+            "AbCdEfGhIjK",
+            "alpha1",
+            1,
+            "7CD34C1040ADD14B",
+            "FACT HOOF AT FIST SITE KENT",
+        ),
+        (
+            "AbCdEfGhIjK",
+            "alpha1",
+            99,
+            "5AA37A81F212146C",
+            "BODE HOP JAKE STOW JUT RAP",
+        ),
+        (
+            "OTP's are good",
+            "correct",
+            0,
+            "F205753943DE4CF9",
+            "ULAN NEW ARMY FUSE SUIT EYED",
+        ),
+        (
+            "OTP's are good",
+            "correct",
+            1,
+            "DDCDAC956F234937",
+            "SKIM CULT LOB SLAM POE HOWL",
+        ),
+        (
+            "OTP's are good",
+            "correct",
+            99,
+            "B203E28FA525BE47",
+            "LONG IVY JULY AJAR BOND LEE",
+        ),
+    ];
+
+    static SHA1_VECTORS: [(&str, &str, u32, &str, &str); 9] = [
+        (
+            "This is a test.",
+            "TeSt",
+            0,
+            "BB9E6AE1979D8FF4",
+            "MILT VARY MAST OK SEES WENT",
+        ),
+        (
+            "This is a test.",
+            "TeSt",
+            1,
+            "63D936639734385B",
+            "CART OTTO HIVE ODE VAT NUT",
+        ),
+        (
+            "This is a test.",
+            "TeSt",
+            99,
+            "87FEC7768B73CCF9",
+            "GAFF WAIT SKID GIG SKY EYED",
+        ),
+        (
+            "AbCdEfGhIjK",
+            "alpha1",
+            0,
+            "AD85F658EBE383C9",
+            "LEST OR HEEL SCOT ROB SUIT",
+        ),
+        (
+            "AbCdEfGhIjK",
+            "alpha1",
+            1,
+            "D07CE229B5CF119B",
+            "RITE TAKE GELD COST TUNE RECK",
+        ),
+        (
+            "AbCdEfGhIjK",
+            "alpha1",
+            99,
+            "27BC71035AAF3DC6",
+            "MAY STAR TIN LYON VEDA STAN",
+        ),
+        (
+            "OTP's are good",
+            "correct",
+            0,
+            "D51F3E99BF8E6F0B",
+            "RUST WELT KICK FELL TAIL FRAU",
+        ),
+        (
+            "OTP's are good",
+            "correct",
+            1,
+            "82AEB52D943774E4",
+            "FLIT DOSE ALSO MEW DRUM DEFY",
+        ),
+        (
+            "OTP's are good",
+            "correct",
+            99,
+            "4F296A74FE1567EC",
+            "AURA ALOE HURL WING BERG WAIT",
+        ),
+    ];
+
+    fn check_vectors(hash: SkeyHash, vectors: &[(&str, &str, u32, &str, &str)]) {
+        for &(passphrase, seed, sequence, hex, words) in vectors {
+            let otp = compute_skey(hash, passphrase, seed, sequence).unwrap();
+            assert_eq!(hex, format_hex(&otp));
+            assert_eq!(words, format_words(&otp));
+            assert_eq!(Ok(otp), parse_words(words));
+            assert_eq!(Ok(otp), parse_response(hex));
+        }
+    }
+
+    #[test]
+    fn rfc2289_md5_vectors() {
+        check_vectors(SkeyHash::MD5, &MD5_VECTORS);
+    }
+
+    #[test]
+    fn rfc2289_sha1_vectors() {
+        check_vectors(SkeyHash::SHA1, &SHA1_VECTORS);
+    }
+
+    #[test]
+    fn dictionary_is_sorted() {
+        let (short, long) = WORDS.split_at(SHORT_WORDS);
+        assert!(short.windows(2).all(|w| w[0] < w[1] && w[0].len() <= 3));
+        assert!(long.windows(2).all(|w| w[0] < w[1] && w[0].len() == 4));
+    }
+
+    #[test]
+    fn response_formats() {
+        let otp = [0x9e, 0x87, 0x61, 0x34, 0xd9, 0x04, 0x99, 0xdd];
+        assert_eq!(Ok(otp), parse_response("9e87 6134 d904 99dd"));
+        assert_eq!(Ok(otp), parse_response("hex:9E876134D90499DD"));
+        assert_eq!(Ok(otp), parse_response("inch sea anne long ahem tour"));
+        assert_eq!(Ok(otp), parse_response("word:INCH SEA ANNE LONG AHEM TOUR"));
+        // Last word changed, the checksum no longer matches
+        assert!(parse_response("INCH SEA ANNE LONG AHEM TOUT").is_err());
+        assert!(parse_response("INCH SEA ANNE LONG AHEM").is_err());
+        assert!(parse_response("INCH SEA ANNE LONG AHEM XYZZY").is_err());
+        assert!(parse_response("hex:9E876134D904").is_err());
+    }
+
+    #[test]
+    fn challenges() {
+        let challenge: SkeyChallenge = "otp-md5 99 TeSt".parse().unwrap();
+        assert_eq!(challenge.hash(), SkeyHash::MD5);
+        assert_eq!(challenge.sequence(), 99);
+        assert_eq!(challenge.seed(), "test");
+        assert_eq!(challenge.to_string(), "otp-md5 99 test");
+        assert_eq!(
+            format_hex(&challenge.response("This is a test.").unwrap()),
+            "50FE1962C4965880"
+        );
+        assert_eq!(challenge.next().unwrap().sequence(), 98);
+        assert_eq!(
+            SkeyChallenge::new(SkeyHash::SHA1, 0, "seed")
+                .unwrap()
+                .next(),
+            None
+        );
+
+        assert!("otp-md4 99 test".parse::<SkeyChallenge>().is_err());
+        assert!("otp-md5 -1 test".parse::<SkeyChallenge>().is_err());
+        assert!("otp-md5 99".parse::<SkeyChallenge>().is_err());
+        assert!("otp-md5 99 te-st".parse::<SkeyChallenge>().is_err());
+        assert!("otp-md5 99 seedlongerthan16chars"
+            .parse::<SkeyChallenge>()
+            .is_err());
+    }
+
+    #[test]
+    fn short_passphrase() {
+        assert!(compute_skey(SkeyHash::MD5, "too short", "test", 0).is_err());
+    }
+
+    #[test]
+    fn verification() {
+        // The host stores sequence 1 and challenges for sequence 0
+        let stored = compute_skey(SkeyHash::SHA1, "This is a test.", "test", 1).unwrap();
+        let response = "MILT VARY MAST OK SEES WENT";
+        assert_eq!(
+            verify_skey(SkeyHash::SHA1, response, &stored).unwrap(),
+            Some(parse_words(response).unwrap())
+        );
+        assert_eq!(
+            verify_skey(SkeyHash::SHA1, "BB9E6AE1979D8FF4", &stored).unwrap(),
+            Some(parse_words(response).unwrap())
+        );
+        // Replaying the stored password itself fails
+        assert_eq!(
+            verify_skey(SkeyHash::SHA1, "CART OTTO HIVE ODE VAT NUT", &stored).unwrap(),
+            None
+        );
+        assert_eq!(verify_skey(SkeyHash::MD5, response, &stored).unwrap(), None);
+        assert!(verify_skey(SkeyHash::SHA1, "not a response", &stored).is_err());
+    }
+}
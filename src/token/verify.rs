@@ -0,0 +1,330 @@
+use crate::token::clock::Clock;
+use crate::token::otp::OtpGenerator;
+use crate::token::TokenError;
+use ring::constant_time;
+
+/// Number of timesteps (TOTP) or counters (HOTP) accepted
+/// before and after the expected one.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Window {
+    pub behind: u32,
+    pub ahead: u32,
+}
+
+impl Window {
+    pub fn new(behind: u32, ahead: u32) -> Self {
+        Window { behind, ahead }
+    }
+}
+
+impl Default for Window {
+    /// One step of clock drift either way, as recommended by RFC 6238 section 5.2
+    fn default() -> Self {
+        Window::new(1, 1)
+    }
+}
+
+/// Verifies codes for a single account.
+///
+/// The last accepted timestep (TOTP) or counter (HOTP) is remembered and
+/// codes at or below it are rejected, so a code can't be replayed
+/// (RFC 6238 section 5.2). Persist [`Verifier::last_accepted`] between
+/// requests and restore it with [`Verifier::with_last_accepted`].
+pub struct Verifier {
+    generator: OtpGenerator,
+    window: Window,
+    last_accepted: Option<i64>,
+}
+
+impl Verifier {
+    pub fn new(generator: OtpGenerator, window: Window) -> Self {
+        Verifier {
+            generator,
+            window,
+            last_accepted: None,
+        }
+    }
+
+    pub fn with_last_accepted(mut self, last_accepted: i64) -> Self {
+        self.last_accepted = Some(last_accepted);
+        self
+    }
+
+    pub fn window(&self) -> Window {
+        self.window
+    }
+
+    pub fn last_accepted(&self) -> Option<i64> {
+        self.last_accepted
+    }
+
+    /// Checks a TOTP code against the timesteps around `time`.
+    /// Returns the timestep that matched.
+    pub fn verify_totp(&mut self, code: &str, time: i64) -> Option<i64> {
+        let timestep = self.generator.timestep(time);
+        self.accept(code, timestep)
+    }
+
+    /// Checks a TOTP code against the timesteps around the clock's time.
+    pub fn verify_totp_now(&mut self, code: &str, clock: &dyn Clock) -> Option<i64> {
+        self.verify_totp(code, clock.now())
+    }
+
+    /// Checks a HOTP code against the counters around `counter`.
+    /// Returns the counter that matched.
+    pub fn verify_hotp(&mut self, code: &str, counter: i64) -> Option<i64> {
+        self.accept(code, counter)
+    }
+
+    fn accept(&mut self, code: &str, expected: i64) -> Option<i64> {
+        let matched = self.find_match(code, expected);
+        if matched.is_some() {
+            self.last_accepted = matched;
+        }
+        matched
+    }
+
+    /// Resynchronises a HOTP token that drifted ahead of the server,
+    /// as described in RFC 4226 section 7.4.
+    ///
+    /// `codes` are consecutive codes read off the token (at least two).
+    /// Counters from `counter` up to `counter + range` are searched for
+    /// the sequence, and the counter following the last code is returned;
+    /// store it as the account's new counter. There is none after `i64::MAX`.
+    pub fn resync_hotp(
+        &mut self,
+        codes: &[&str],
+        counter: i64,
+        range: u32,
+    ) -> Result<Option<i64>, TokenError> {
+        if codes.len() < 2 {
+            return Err(TokenError::TooFewResyncCodes(codes.len()));
+        }
+        let mut first = counter;
+        if let Some(last_accepted) = self.last_accepted {
+            first = first.max(last_accepted.saturating_add(1));
+        }
+        let last = counter.saturating_add(i64::from(range));
+        if first > last {
+            return Ok(None);
+        }
+
+        // Each counter's code is generated once. Like find_match, every start
+        // is compared, so the time taken does not reveal where the sequence was.
+        let sequence_length = codes.len() as i64;
+        let candidates: Vec<String> = (first..=last.saturating_add(sequence_length - 1))
+            .map(|step| self.generator.hotp(step))
+            .collect();
+        let mut matched = None;
+        for (offset, window) in candidates.windows(codes.len()).enumerate() {
+            let mut sequence_matches = true;
+            for (candidate, code) in window.iter().zip(codes) {
+                sequence_matches &=
+                    constant_time::verify_slices_are_equal(candidate.as_bytes(), code.as_bytes())
+                        .is_ok();
+            }
+            if sequence_matches && matched.is_none() {
+                matched = Some(first + (offset as i64 + sequence_length - 1));
+            }
+        }
+        // No counter follows a sequence ending at i64::MAX
+        let next = matched.and_then(|last_code: i64| last_code.checked_add(1));
+        if next.is_some() {
+            self.last_accepted = matched;
+        }
+        Ok(next)
+    }
+
+    fn matches_step(&self, code: &str, step: i64) -> bool {
+        let candidate = self.generator.hotp(step);
+        constant_time::verify_slices_are_equal(candidate.as_bytes(), code.as_bytes()).is_ok()
+    }
+
+    fn find_match(&self, code: &str, expected: i64) -> Option<i64> {
+        if code.len() != usize::from(self.generator.digits()) {
+            return None;
+        }
+        let mut first = expected.saturating_sub(i64::from(self.window.behind));
+        if let Some(last_accepted) = self.last_accepted {
+            first = first.max(last_accepted.saturating_add(1));
+        }
+        let last = expected.saturating_add(i64::from(self.window.ahead));
+        // Every candidate is checked, so the time taken
+        // does not reveal which step matched.
+        let mut matched = None;
+        for step in first..=last {
+            if self.matches_step(code, step) && matched.is_none() {
+                matched = Some(step);
+            }
+        }
+        matched
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::HmacHash;
+
+    fn rfc_generator(digits: u8) -> OtpGenerator {
+        OtpGenerator::new(b"12345678901234567890", HmacHash::SHA1, digits, 30).unwrap()
+    }
+
+    #[test]
+    fn totp_window() {
+        // "07081804" is the code for timestep 37037036
+        let mut verifier = Verifier::new(rfc_generator(8), Window::default());
+        assert_eq!(verifier.verify_totp("07081804", 1111111109), Some(37037036));
+        let mut verifier = Verifier::new(rfc_generator(8), Window::default());
+        assert_eq!(
+            verifier.verify_totp("07081804", 1111111109 + 30),
+            Some(37037036)
+        );
+        let mut verifier = Verifier::new(rfc_generator(8), Window::default());
+        assert_eq!(
+            verifier.verify_totp("07081804", 1111111109 - 30),
+            Some(37037036)
+        );
+        let mut verifier = Verifier::new(rfc_generator(8), Window::default());
+        assert_eq!(verifier.verify_totp("07081804", 1111111109 + 60), None);
+        assert_eq!(verifier.verify_totp("07081804", 1111111109 - 60), None);
+    }
+
+    #[test]
+    fn totp_no_drift() {
+        let mut verifier = Verifier::new(rfc_generator(8), Window::new(0, 0));
+        assert_eq!(verifier.verify_totp("94287082", 60), None);
+        assert_eq!(verifier.verify_totp("94287082", 59), Some(1));
+    }
+
+    #[test]
+    fn totp_with_clock_and_start_time() {
+        use crate::token::clock::FixedClock;
+
+        let generator = rfc_generator(8).with_start_time(-30);
+        let mut verifier = Verifier::new(generator, Window::new(0, 0));
+        // Timestep 1 now starts at unix time 0
+        assert_eq!(
+            verifier.verify_totp_now("94287082", &FixedClock::new(0)),
+            Some(1)
+        );
+    }
+
+    #[test]
+    fn hotp_look_ahead() {
+        let mut verifier = Verifier::new(rfc_generator(6), Window::new(0, 5));
+        assert_eq!(verifier.verify_hotp("287922", 0), None);
+        assert_eq!(verifier.verify_hotp("755224", 1), None);
+        assert_eq!(verifier.verify_hotp("755224", 0), Some(0));
+        assert_eq!(verifier.verify_hotp("254676", 1), Some(5));
+    }
+
+    #[test]
+    fn hotp_hex_encoding() {
+        use crate::token::otp::OtpEncoding;
+
+        let generator = rfc_generator(6).with_encoding(OtpEncoding::Hex);
+        let mut verifier = Verifier::new(generator, Window::new(0, 1));
+        assert_eq!(verifier.verify_hotp("755224", 0), None);
+        assert_eq!(verifier.verify_hotp("93cf18", 0), Some(0));
+    }
+
+    #[test]
+    fn malformed_codes() {
+        let mut verifier = Verifier::new(rfc_generator(6), Window::new(0, 5));
+        assert_eq!(verifier.verify_hotp("75522", 0), None);
+        assert_eq!(verifier.verify_hotp("7552240", 0), None);
+        assert_eq!(verifier.verify_hotp("", 0), None);
+    }
+
+    #[test]
+    fn totp_replay_rejected() {
+        let mut verifier = Verifier::new(rfc_generator(8), Window::default());
+        assert_eq!(verifier.last_accepted(), None);
+        assert_eq!(verifier.verify_totp("07081804", 1111111109), Some(37037036));
+        assert_eq!(verifier.last_accepted(), Some(37037036));
+        // Same code again, still inside the window
+        assert_eq!(verifier.verify_totp("07081804", 1111111109), None);
+        assert_eq!(verifier.verify_totp("07081804", 1111111109 + 30), None);
+        // Code for the following timestep is still accepted
+        assert_eq!(verifier.verify_totp("14050471", 1111111111), Some(37037037));
+    }
+
+    #[test]
+    fn hotp_replay_rejected() {
+        let mut verifier = Verifier::new(rfc_generator(6), Window::new(0, 5));
+        assert_eq!(verifier.verify_hotp("359152", 0), Some(2));
+        assert_eq!(verifier.verify_hotp("359152", 0), None);
+        // Older counters are rejected even if they are in the window
+        assert_eq!(verifier.verify_hotp("287082", 0), None);
+        assert_eq!(verifier.verify_hotp("969429", 0), Some(3));
+    }
+
+    #[test]
+    fn restored_state() {
+        let mut verifier = Verifier::new(rfc_generator(6), Window::new(0, 5)).with_last_accepted(2);
+        assert_eq!(verifier.verify_hotp("359152", 0), None);
+        assert_eq!(verifier.verify_hotp("969429", 0), Some(3));
+        assert_eq!(verifier.last_accepted(), Some(3));
+    }
+
+    #[test]
+    fn hotp_resync() {
+        // RFC 4226 appendix D, counters 5 to 9
+        let codes = ["254676", "287922", "162583", "399871", "520489"];
+        let mut verifier = Verifier::new(rfc_generator(6), Window::new(0, 1));
+        // Out of reach of the normal look-ahead window
+        assert_eq!(verifier.verify_hotp("287922", 0), None);
+        assert_eq!(verifier.resync_hotp(&codes[1..3], 0, 100), Ok(Some(8)));
+        assert_eq!(verifier.last_accepted(), Some(7));
+        assert_eq!(verifier.verify_hotp("399871", 8), Some(8));
+    }
+
+    #[test]
+    fn hotp_resync_three_codes() {
+        let codes = ["254676", "287922", "162583", "399871", "520489"];
+        let mut verifier = Verifier::new(rfc_generator(6), Window::new(0, 1));
+        assert_eq!(verifier.resync_hotp(&codes[2..], 0, 100), Ok(Some(10)));
+    }
+
+    #[test]
+    fn hotp_resync_failures() {
+        let codes = ["254676", "287922", "162583", "399871", "520489"];
+        let mut verifier = Verifier::new(rfc_generator(6), Window::new(0, 1));
+        // Not consecutive
+        assert_eq!(
+            verifier.resync_hotp(&[codes[0], codes[2]], 0, 100),
+            Ok(None)
+        );
+        // Outside the searched range
+        assert_eq!(verifier.resync_hotp(&codes[3..], 0, 5), Ok(None));
+        assert_eq!(
+            verifier.resync_hotp(&codes[..1], 0, 100),
+            Err(TokenError::TooFewResyncCodes(1))
+        );
+        // Counters at or below the last accepted one are not reused
+        let mut verifier = verifier.with_last_accepted(6);
+        assert_eq!(verifier.resync_hotp(&codes[1..3], 0, 100), Ok(None));
+        assert_eq!(verifier.resync_hotp(&codes[2..4], 0, 100), Ok(Some(9)));
+    }
+
+    #[test]
+    fn hotp_resync_at_counter_limit() {
+        let generator = rfc_generator(6);
+        let codes: Vec<String> = (i64::MAX - 2..=i64::MAX)
+            .map(|counter| generator.hotp(counter))
+            .collect();
+        let codes: Vec<&str> = codes.iter().map(String::as_str).collect();
+        let mut verifier = Verifier::new(generator, Window::new(0, 1));
+        // No counter follows i64::MAX
+        assert_eq!(
+            verifier.resync_hotp(&codes[1..], i64::MAX - 10, 100),
+            Ok(None)
+        );
+        assert_eq!(verifier.last_accepted(), None);
+        assert_eq!(
+            verifier.resync_hotp(&codes[..2], i64::MAX - 10, 100),
+            Ok(Some(i64::MAX))
+        );
+    }
+}
@@ -0,0 +1,340 @@
+use crate::token::TokenError;
+use aes::cipher::{Array, BlockCipherDecrypt, KeyInit};
+use aes::Aes128;
+use byteorder::{ByteOrder, LittleEndian};
+use ring::constant_time;
+
+const MODHEX_ALPHABET: &[u8; 16] = b"cbdefghijklnrtuv";
+// The encrypted part of an OTP, 16 bytes in modhex
+const TOKEN_LENGTH: usize = 32;
+// Public IDs are usually 6 bytes, giving 44 character OTPs
+const MAX_PUBLIC_ID_LENGTH: usize = 32;
+// CRC-16 of a block that ends with its own (complemented) CRC
+const CRC_RESIDUE: u16 = 0xf0b8;
+// The top bit of the usage counter is a flag, not part of the count
+const USAGE_COUNTER_MASK: u16 = 0x7fff;
+
+/// Decodes modhex, the keyboard layout independent hex used by YubiKeys.
+pub fn decode_modhex(modhex: &str) -> Result<Vec<u8>, TokenError> {
+    if !modhex.len().is_multiple_of(2) {
+        return Err(TokenError::InvalidYubicoOtp(
+            "odd number of modhex characters".to_string(),
+        ));
+    }
+    let nibble = |c: u8| {
+        MODHEX_ALPHABET
+            .iter()
+            .position(|&m| m == c.to_ascii_lowercase())
+            .map(|n| n as u8)
+            .ok_or_else(|| {
+                TokenError::InvalidYubicoOtp(format!("invalid modhex character: {}", c as char))
+            })
+    };
+    modhex
+        .as_bytes()
+        .chunks(2)
+        .map(|pair| Ok(nibble(pair[0])? << 4 | nibble(pair[1])?))
+        .collect()
+}
+
+pub fn encode_modhex(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .flat_map(|b| [b >> 4, b & 0xf])
+        .map(|n| MODHEX_ALPHABET[usize::from(n)] as char)
+        .collect()
+}
+
+// CRC-16/ISO 13239, as used by the YubiKey firmware
+fn crc16(data: &[u8]) -> u16 {
+    let mut crc = 0xffffu16;
+    for &byte in data {
+        crc ^= u16::from(byte);
+        for _ in 0..8 {
+            let carry = crc & 1;
+            crc >>= 1;
+            if carry != 0 {
+                crc ^= 0x8408;
+            }
+        }
+    }
+    crc
+}
+
+/// A decrypted Yubico OTP.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct YubicoOtp {
+    public_id: String,
+    private_id: [u8; 6],
+    usage_counter: u16,
+    timestamp: u32,
+    session_counter: u8,
+    random: u16,
+}
+
+impl YubicoOtp {
+    pub fn public_id(&self) -> &str {
+        &self.public_id
+    }
+
+    pub fn private_id(&self) -> [u8; 6] {
+        self.private_id
+    }
+
+    /// Incremented each time the YubiKey is powered up.
+    pub fn usage_counter(&self) -> u16 {
+        self.usage_counter
+    }
+
+    /// 8 Hz timer started at power up, wraps around after about 24 days.
+    pub fn timestamp(&self) -> u32 {
+        self.timestamp
+    }
+
+    /// Incremented for each OTP within a power up, reset with the usage counter.
+    pub fn session_counter(&self) -> u8 {
+        self.session_counter
+    }
+
+    pub fn random(&self) -> u16 {
+        self.random
+    }
+
+    /// The usage and session counters, in the order they must increase.
+    pub fn counter(&self) -> (u16, u8) {
+        (self.usage_counter, self.session_counter)
+    }
+}
+
+/// The credentials of a YubiKey slot programmed for Yubico OTP.
+pub struct YubicoKey {
+    public_id: String,
+    private_id: [u8; 6],
+    cipher: Aes128,
+}
+
+impl YubicoKey {
+    pub fn new(
+        public_id: &str,
+        private_id: [u8; 6],
+        aes_key: [u8; 16],
+    ) -> Result<Self, TokenError> {
+        if public_id.len() > MAX_PUBLIC_ID_LENGTH {
+            return Err(TokenError::InvalidYubicoOtp(
+                "public id is longer than 16 bytes".to_string(),
+            ));
+        }
+        decode_modhex(public_id)?;
+        Ok(YubicoKey {
+            public_id: public_id.to_ascii_lowercase(),
+            private_id,
+            cipher: Aes128::new(&Array::from(aes_key)),
+        })
+    }
+
+    pub fn public_id(&self) -> &str {
+        &self.public_id
+    }
+
+    /// Decrypts an OTP and checks its CRC.
+    /// The public and private IDs are not compared, see [`YubicoVerifier`].
+    pub fn decrypt(&self, otp: &str) -> Result<YubicoOtp, TokenError> {
+        let otp = otp.trim();
+        if !otp.is_ascii() {
+            return Err(TokenError::InvalidYubicoOtp(
+                "otp is not modhex".to_string(),
+            ));
+        }
+        if otp.len() < TOKEN_LENGTH || otp.len() > TOKEN_LENGTH + MAX_PUBLIC_ID_LENGTH {
+            return Err(TokenError::InvalidYubicoOtp(format!(
+                "unexpected length: {}",
+                otp.len()
+            )));
+        }
+        let (public_id, token) = otp.split_at(otp.len() - TOKEN_LENGTH);
+        decode_modhex(public_id)?;
+        let mut block = Array::try_from(decode_modhex(token)?.as_slice())
+            .map_err(|_| TokenError::InvalidYubicoOtp("malformed token".to_string()))?;
+        self.cipher.decrypt_block(&mut block);
+
+        if crc16(&block) != CRC_RESIDUE {
+            return Err(TokenError::InvalidYubicoOtp(
+                "crc mismatch, wrong key or corrupted otp".to_string(),
+            ));
+        }
+        // uid (6) | usage counter (2) | timestamp (3) | session counter (1) | random (2) | crc (2)
+        // with all the integers in little endian order
+        let mut private_id = [0u8; 6];
+        private_id.copy_from_slice(&block[..6]);
+        Ok(YubicoOtp {
+            public_id: public_id.to_ascii_lowercase(),
+            private_id,
+            usage_counter: LittleEndian::read_u16(&block[6..8]) & USAGE_COUNTER_MASK,
+            timestamp: LittleEndian::read_u24(&block[8..11]),
+            session_counter: block[11],
+            random: LittleEndian::read_u16(&block[12..14]),
+        })
+    }
+}
+
+/// Verifies Yubico OTPs for a single YubiKey without a validation server.
+///
+/// The usage and session counters of the last accepted OTP are remembered
+/// and OTPs that don't move them forward are rejected as replays.
+/// Persist [`YubicoVerifier::last_accepted`] between requests and restore it
+/// with [`YubicoVerifier::with_last_accepted`].
+pub struct YubicoVerifier {
+    key: YubicoKey,
+    last_accepted: Option<(u16, u8)>,
+}
+
+impl YubicoVerifier {
+    pub fn new(key: YubicoKey) -> Self {
+        YubicoVerifier {
+            key,
+            last_accepted: None,
+        }
+    }
+
+    pub fn with_last_accepted(mut self, usage_counter: u16, session_counter: u8) -> Self {
+        self.last_accepted = Some((usage_counter, session_counter));
+        self
+    }
+
+    pub fn last_accepted(&self) -> Option<(u16, u8)> {
+        self.last_accepted
+    }
+
+    /// Returns the decrypted OTP if it belongs to this key and has not been seen.
+    /// Malformed OTPs and CRC mismatches are errors.
+    pub fn verify(&mut self, otp: &str) -> Result<Option<YubicoOtp>, TokenError> {
+        let decrypted = self.key.decrypt(otp)?;
+        let same_key = decrypted.public_id == self.key.public_id
+            && constant_time::verify_slices_are_equal(&decrypted.private_id, &self.key.private_id)
+                .is_ok();
+        if !same_key {
+            return Ok(None);
+        }
+        if self
+            .last_accepted
+            .is_some_and(|last| decrypted.counter() <= last)
+        {
+            return Ok(None);
+        }
+        self.last_accepted = Some(decrypted.counter());
+        Ok(Some(decrypted))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use aes::cipher::BlockCipherEncrypt;
+
+    const AES_KEY: [u8; 16] = [
+        0xec, 0xde, 0x18, 0xdb, 0xe7, 0x6f, 0xbd, 0x0c, 0x33, 0x33, 0x0f, 0x1c, 0x35, 0x48, 0x71,
+        0xdb,
+    ];
+    const PRIVATE_ID: [u8; 6] = [0x87, 0x92, 0xeb, 0xfe, 0x26, 0xcc];
+    const PUBLIC_ID: &str = "vvccccfhcbdj";
+
+    fn make_otp(usage_counter: u16, session_counter: u8) -> String {
+        let mut block = [0u8; 16];
+        block[..6].copy_from_slice(&PRIVATE_ID);
+        LittleEndian::write_u16(&mut block[6..8], usage_counter);
+        LittleEndian::write_u24(&mut block[8..11], 0x0a0b0c);
+        block[11] = session_counter;
+        LittleEndian::write_u16(&mut block[12..14], 0x1234);
+        let crc = !crc16(&block[..14]);
+        LittleEndian::write_u16(&mut block[14..], crc);
+
+        let mut block = Array::from(block);
+        Aes128::new(&Array::from(AES_KEY)).encrypt_block(&mut block);
+        format!("{PUBLIC_ID}{}", encode_modhex(&block))
+    }
+
+    fn verifier() -> YubicoVerifier {
+        YubicoVerifier::new(YubicoKey::new(PUBLIC_ID, PRIVATE_ID, AES_KEY).unwrap())
+    }
+
+    #[test]
+    fn modhex() {
+        assert_eq!(encode_modhex(&[0x01, 0x23, 0x45, 0x67, 0x89]), "cbdefghijk");
+        assert_eq!(encode_modhex(&[0xab, 0xcd, 0xef]), "lnrtuv");
+        assert_eq!(
+            decode_modhex("CBDEFGHIJK").unwrap(),
+            [0x01, 0x23, 0x45, 0x67, 0x89]
+        );
+        assert!(decode_modhex("cbd").is_err());
+        assert!(decode_modhex("cbda").is_err());
+    }
+
+    #[test]
+    fn yubico_c_vector() {
+        // From the yubico-c test suite, with an 8 character public ID
+        let key = YubicoKey::new("dteffuje", PRIVATE_ID, AES_KEY).unwrap();
+        let otp = key
+            .decrypt("dteffujehknhfjbrjnlnldnhcujvddbikngjrtgh")
+            .unwrap();
+        assert_eq!(otp.public_id(), "dteffuje");
+        assert_eq!(otp.private_id(), PRIVATE_ID);
+        assert_eq!(otp.usage_counter(), 19);
+        assert_eq!(otp.timestamp(), 0x00c230);
+        assert_eq!(otp.session_counter(), 17);
+        assert_eq!(otp.random(), 0x9fc8);
+    }
+
+    #[test]
+    fn generated_otp() {
+        let otp = make_otp(5, 2);
+        assert_eq!(otp.len(), 44);
+        let decrypted = verifier().verify(&otp).unwrap().unwrap();
+        assert_eq!(decrypted.counter(), (5, 2));
+        assert_eq!(decrypted.timestamp(), 0x0a0b0c);
+        // The flag bit is not part of the usage counter
+        let decrypted = verifier().verify(&make_otp(0x8005, 2)).unwrap().unwrap();
+        assert_eq!(decrypted.usage_counter(), 5);
+    }
+
+    #[test]
+    fn counters_must_increase() {
+        let mut verifier = verifier();
+        assert!(verifier.verify(&make_otp(5, 2)).unwrap().is_some());
+        assert_eq!(verifier.last_accepted(), Some((5, 2)));
+        assert_eq!(verifier.verify(&make_otp(5, 2)).unwrap(), None);
+        assert_eq!(verifier.verify(&make_otp(5, 1)).unwrap(), None);
+        assert_eq!(verifier.verify(&make_otp(4, 9)).unwrap(), None);
+        assert!(verifier.verify(&make_otp(5, 3)).unwrap().is_some());
+        // A new power up resets the session counter
+        assert!(verifier.verify(&make_otp(6, 0)).unwrap().is_some());
+
+        let mut verifier = self::verifier().with_last_accepted(6, 0);
+        assert_eq!(verifier.verify(&make_otp(6, 0)).unwrap(), None);
+        assert!(verifier.verify(&make_otp(6, 1)).unwrap().is_some());
+    }
+
+    #[test]
+    fn wrong_key() {
+        let otp = make_otp(5, 2);
+        // Different public ID
+        let key = YubicoKey::new("vvccccfhcbdk", PRIVATE_ID, AES_KEY).unwrap();
+        assert_eq!(YubicoVerifier::new(key).verify(&otp).unwrap(), None);
+        // Different private ID
+        let key = YubicoKey::new(PUBLIC_ID, [0; 6], AES_KEY).unwrap();
+        assert_eq!(YubicoVerifier::new(key).verify(&otp).unwrap(), None);
+        // Different AES key, the CRC doesn't match
+        let key = YubicoKey::new(PUBLIC_ID, PRIVATE_ID, [0; 16]).unwrap();
+        assert!(YubicoVerifier::new(key).verify(&otp).is_err());
+    }
+
+    #[test]
+    fn malformed_otps() {
+        let otp = make_otp(5, 2);
+        let mut verifier = verifier();
+        assert!(verifier.verify(&otp[..43]).is_err());
+        assert!(verifier.verify(&otp[12..40]).is_err());
+        assert!(verifier.verify(&otp.replace('c', "x")).is_err());
+        assert!(verifier.verify(&otp.replace('c', "é")).is_err());
+        assert!(YubicoKey::new("vvccccfhcbd", PRIVATE_ID, AES_KEY).is_err());
+    }
+}